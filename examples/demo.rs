@@ -3,10 +3,10 @@ extern crate capstone;
 static CODE: &'static [u8] = b"\x55\x48\x8b\x05\xb8\x13\x00\x00";
 
 fn main() {
-    match capstone::Capstone::new(capstone::CsArch::ARCH_X86,
-                                  capstone::CsMode::MODE_64) {
-        Some(cs) => {
-            if let Some(insns) = cs.disasm(CODE, 0x1000, 0) {
+    match capstone::HandleBuilder::new(capstone::CsArch::ARCH_X86,
+                                       capstone::mode::W64).build() {
+        Ok(cs) => {
+            if let Ok(insns) = cs.disasm(CODE, 0x1000, 0) {
                 println!("Got {} instructions", insns.len());
 
                 for i in insns.iter() {
@@ -14,8 +14,8 @@ fn main() {
                 }
             }
         },
-        None => {
-            println!("Ohnoes");
+        Err(err) => {
+            println!("Ohnoes: {}", err);
         }
     }
 }
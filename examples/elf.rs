@@ -0,0 +1,30 @@
+//! Disassemble the .text section of an ELF file:
+//!
+//!     cargo run --features object --example elf -- /bin/true
+extern crate capstone;
+extern crate object;
+
+use std::env;
+use std::fs;
+use object::{Object, ObjectSection};
+use object::read::elf::{ElfFile64, FileHeader};
+
+fn main() {
+    let path = env::args().nth(1).expect("usage: elf <file>");
+    let data = fs::read(&path).expect("couldn't read file");
+    let elf = ElfFile64::<object::Endianness>::parse(&*data).expect("not a 64-bit ELF file");
+
+    let e_machine = elf.elf_header().e_machine(elf.endian());
+    let (arch, mut mode) = capstone::mode_from_elf_machine(e_machine, true)
+        .expect("unsupported machine");
+    if elf.endian() == object::Endianness::Big {
+        mode = mode | capstone::mode::BIG_ENDIAN;
+    }
+
+    let text = elf.section_by_name(".text").expect("no .text section");
+    let cs = capstone::HandleBuilder::new(arch, mode).build().expect("couldn't open capstone");
+    let insns = cs.disasm_section(text.data().unwrap(), text.address()).expect("couldn't disassemble");
+    for insn in insns.iter() {
+        println!("{:x}: {} {}", insn.address, insn.mnemonic().unwrap_or(""), insn.op_str().unwrap_or(""));
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use ffi::{CsArch, CsMode};
+use owned::OwnedInsn;
+
+// (arch, mode, code, address) -> (last use tick, instructions)
+type CacheKey = (CsArch, CsMode, Vec<u8>, u64);
+type CacheEntry = (u64, Vec<OwnedInsn>);
+
+/// Least-recently-used cache of disassembly results, for `Handle::disasm_cached`
+///
+/// Entries are keyed on the arch, mode, code bytes and start address, so
+/// handles for different targets can share a cache. Other options (detail,
+/// syntax, SKIPDATA) are not part of the key, so handles sharing a cache must
+/// agree on them.
+pub struct DisasmCache {
+    capacity: usize,
+    tick: u64,
+    hits: usize,
+    misses: usize,
+    entries: HashMap<CacheKey, CacheEntry>,
+}
+
+impl DisasmCache {
+    /// Create a cache holding at most `capacity` disassembly results
+    pub fn new(capacity: usize) -> DisasmCache {
+        DisasmCache {
+            capacity: capacity,
+            tick: 0,
+            hits: 0,
+            misses: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Number of lookups served from the cache
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Number of lookups that had to go to the engine
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// Number of cached results
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the cache holds no results
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Drop all cached results
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    #[doc(hidden)]
+    pub fn get(&mut self, arch: CsArch, mode: CsMode, code: &[u8], addr: u64) -> Option<Vec<OwnedInsn>> {
+        self.tick += 1;
+        let tick = self.tick;
+        match self.entries.get_mut(&(arch, mode, code.to_vec(), addr)) {
+            Some(entry) => {
+                self.hits += 1;
+                entry.0 = tick;
+                Some(entry.1.clone())
+            },
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn insert(&mut self, arch: CsArch, mode: CsMode, code: &[u8], addr: u64, insns: Vec<OwnedInsn>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = (arch, mode, code.to_vec(), addr);
+        // Replacing an existing entry doesn't grow the cache
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let oldest = self.entries.iter()
+                .min_by_key(|&(_, &(tick, _))| tick)
+                .map(|(oldest, _)| oldest.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.entries.insert(key, (self.tick, insns));
+    }
+}
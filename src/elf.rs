@@ -0,0 +1,35 @@
+use ffi::{CsArch, CsMode, mode};
+
+const EM_SPARC: u16 = 2;
+const EM_386: u16 = 3;
+const EM_MIPS: u16 = 8;
+const EM_PPC: u16 = 20;
+const EM_PPC64: u16 = 21;
+const EM_S390: u16 = 22;
+const EM_ARM: u16 = 40;
+const EM_SPARCV9: u16 = 43;
+const EM_X86_64: u16 = 62;
+const EM_AARCH64: u16 = 183;
+const EM_XCORE: u16 = 203;
+
+/// Pick the Capstone arch and mode for an ELF `e_machine` value
+///
+/// `is_64` is whether the file is ELFCLASS64. Byte order is not part of
+/// `e_machine`, so for bi-endian architectures (ARM, MIPS, PPC) the caller
+/// must add `mode::BIG_ENDIAN` for an ELFDATA2MSB file.
+pub fn mode_from_elf_machine(e_machine: u16, is_64: bool) -> Option<(CsArch, CsMode)> {
+    match e_machine {
+        EM_386 => Some((CsArch::ARCH_X86, mode::W32)),
+        EM_X86_64 => Some((CsArch::ARCH_X86, if is_64 { mode::W64 } else { mode::W32 })),
+        EM_ARM => Some((CsArch::ARCH_ARM, mode::ARM)),
+        EM_AARCH64 => Some((CsArch::ARCH_ARM64, mode::LITTLE_ENDIAN)),
+        EM_MIPS => Some((CsArch::ARCH_MIPS, if is_64 { mode::MIPS64 } else { mode::MIPS32 })),
+        EM_PPC => Some((CsArch::ARCH_PPC, mode::W32)),
+        EM_PPC64 => Some((CsArch::ARCH_PPC, mode::W64)),
+        EM_SPARC => Some((CsArch::ARCH_SPARC, mode::BIG_ENDIAN)),
+        EM_SPARCV9 => Some((CsArch::ARCH_SPARC, mode::V9 | mode::BIG_ENDIAN)),
+        EM_S390 => Some((CsArch::ARCH_SYSZ, mode::BIG_ENDIAN)),
+        EM_XCORE => Some((CsArch::ARCH_XCORE, mode::LITTLE_ENDIAN)),
+        _ => None,
+    }
+}
@@ -1,6 +1,7 @@
 use std;
 use libc;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::mem;
 use std::str;
 
@@ -8,7 +9,7 @@ use std::str;
 pub type CsHandle = libc::size_t;
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 /// Capstone architectures
 pub enum CsArch {
     /// ARM architecture (including Thumb, Thumb-2)
@@ -31,6 +32,23 @@ pub enum CsArch {
     ARCH_ALL = 0xFFFF,
 }
 
+impl fmt::Display for CsArch {
+    fn fmt(&self, w: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            CsArch::ARCH_ARM => "arm",
+            CsArch::ARCH_ARM64 => "aarch64",
+            CsArch::ARCH_MIPS => "mips",
+            CsArch::ARCH_X86 => "x86",
+            CsArch::ARCH_PPC => "ppc",
+            CsArch::ARCH_SPARC => "sparc",
+            CsArch::ARCH_SYSZ => "sysz",
+            CsArch::ARCH_XCORE => "xcore",
+            CsArch::ARCH_ALL => "all",
+        };
+        write!(w, "{}", name)
+    }
+}
+
 pub use ffi::mode::CsMode;
 /// Capstone architecture modes
 pub mod mode {
@@ -72,6 +90,69 @@ pub mod mode {
             const MIPS64 = W64.bits,
         }
     }
+
+    use std::fmt;
+    use ffi::CsArch;
+
+    fn arch_flags(arch: CsArch) -> &'static [(CsMode, &'static str)] {
+        match arch {
+            CsArch::ARCH_ARM => &[(THUMB, "THUMB"), (MCLASS, "MCLASS"), (V8, "V8")],
+            CsArch::ARCH_MIPS => &[(MICRO, "MICRO"), (MIPS3, "MIPS3"), (MIPS32R6, "MIPS32R6"),
+                                   (MIPSGP64, "MIPSGP64"), (MIPS32, "MIPS32"), (MIPS64, "MIPS64")],
+            CsArch::ARCH_X86 => &[(W16, "W16"), (W32, "W32"), (W64, "W64")],
+            CsArch::ARCH_PPC => &[(W32, "W32"), (W64, "W64")],
+            CsArch::ARCH_SPARC => &[(V9, "V9")],
+            CsArch::ARCH_ALL => &[(W16, "W16"), (W32, "W32"), (W64, "W64")],
+            _ => &[],
+        }
+    }
+
+    impl CsMode {
+        /// Whether every flag set in this mode applies to `arch`
+        pub fn is_valid_for(&self, arch: CsArch) -> bool {
+            let mut allowed = arch_flags(arch).iter().fold(CsMode::empty(), |acc, &(flag, _)| acc | flag);
+            if arch != CsArch::ARCH_X86 {
+                allowed.insert(BIG_ENDIAN);
+            }
+            allowed.contains(*self)
+        }
+
+        /// Render the set mode flags as a `|`-joined string of flag names.
+        ///
+        /// Several flags share the same bit on different architectures, so the
+        /// architecture is needed to pick the right names. Bits that have no
+        /// meaning for `arch` are shown in hex; `ARCH_ALL` only names the
+        /// generic width flags.
+        pub fn describe(&self, arch: CsArch) -> String {
+            let mut names = Vec::new();
+            let mut rest = *self - BIG_ENDIAN;
+            for &(flag, name) in arch_flags(arch) {
+                if self.contains(flag) {
+                    names.push(name.to_string());
+                    rest.remove(flag);
+                }
+            }
+            if let CsArch::ARCH_ARM = arch {
+                if !self.contains(THUMB) {
+                    names.insert(0, "ARM".to_string());
+                }
+            }
+            if !rest.is_empty() {
+                names.push(format!("{:#x}", rest.bits()));
+            }
+            names.push(match self.contains(BIG_ENDIAN) {
+                true => "BIG_ENDIAN",
+                false => "LITTLE_ENDIAN",
+            }.to_string());
+            names.join("|")
+        }
+    }
+
+    impl fmt::Display for CsMode {
+        fn fmt(&self, w: &mut fmt::Formatter) -> fmt::Result {
+            write!(w, "{}", self.describe(CsArch::ARCH_ALL))
+        }
+    }
 }
 
 #[repr(C)]
@@ -93,13 +174,50 @@ pub enum CsOptType {
     CS_OPT_SKIPDATA_SETUP,
 }
 
+/// User-defined `malloc` for CS_OPT_MEM
+pub type CsMalloc = extern "C" fn(size: libc::size_t) -> *mut libc::c_void;
+/// User-defined `calloc` for CS_OPT_MEM
+pub type CsCalloc = extern "C" fn(nmemb: libc::size_t, size: libc::size_t) -> *mut libc::c_void;
+/// User-defined `realloc` for CS_OPT_MEM
+pub type CsRealloc = extern "C" fn(ptr: *mut libc::c_void, size: libc::size_t) -> *mut libc::c_void;
+/// User-defined `free` for CS_OPT_MEM
+pub type CsFree = extern "C" fn(ptr: *mut libc::c_void);
+/// User-defined `vsnprintf` for CS_OPT_MEM (the last argument is a `va_list`)
+pub type CsVsnprintf = extern "C" fn(buf: *mut libc::c_char, len: libc::size_t, fmt: *const libc::c_char,
+                                     ap: *mut libc::c_void) -> libc::c_int;
+
+#[repr(C)]
+/// User-defined dynamic memory functions (cs_opt_mem)
+pub struct CsOptMem {
+    pub malloc: CsMalloc,
+    pub calloc: CsCalloc,
+    pub realloc: CsRealloc,
+    pub free: CsFree,
+    pub vsnprintf: CsVsnprintf,
+}
+
+/// User-defined callback for CS_OPT_SKIPDATA: returns the number of bytes
+/// to skip at `offset`, or 0 to stop disassembling
+pub type CsSkipdataCallback = extern "C" fn(code: *const u8, code_size: libc::size_t, offset: libc::size_t,
+                                            user_data: *mut libc::c_void) -> libc::size_t;
+
+#[repr(C)]
+/// Setup for CS_OPT_SKIPDATA_SETUP (cs_opt_skipdata)
+pub struct CsOptSkipdata {
+    /// Mnemonic for skipped data, or null for the default ".byte"
+    pub mnemonic: *const libc::c_char,
+    /// Callback deciding how many bytes to skip, or None for the default
+    pub callback: Option<CsSkipdataCallback>,
+    pub user_data: *mut libc::c_void,
+}
+
 pub use ffi::optval::CsOptValue;
 #[allow(dead_code)]
 pub mod optval {
     use std::fmt;
     #[repr(C)]
-    #[derive(PartialEq, Eq)]
-    pub struct CsOptValue(pub u32);
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub struct CsOptValue(pub usize);
     /// Turn OFF an option
     pub const CS_OPT_OFF: CsOptValue = CsOptValue(0);
     /// Turn ON an option
@@ -113,6 +231,14 @@ pub mod optval {
     /// Print numbers instead of register names
     pub const CS_OPT_SYNTAX_NOREGNAME: CsOptValue = CsOptValue(3);
 
+    /// CS_OPT_ON or CS_OPT_OFF depending on `enable`
+    pub fn from_bool(enable: bool) -> CsOptValue {
+        match enable {
+            true => CS_OPT_ON,
+            false => CS_OPT_OFF,
+        }
+    }
+
     impl fmt::Debug for CsOptValue {
         fn fmt(&self, w: &mut fmt::Formatter) -> fmt::Result {
             write!(w, "{}", self.0)
@@ -132,7 +258,6 @@ pub mod optval {
     }
 }
 
-//FIXME Debug print shows unknown groups as IRET
 #[allow(non_camel_case_types)]
 #[repr(u8)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -152,6 +277,15 @@ pub enum CsGroup {
     CS_GRP_IRET,
 }
 
+impl CsGroup {
+    /// The generic group with raw id `id`, or None for arch-specific ids
+    pub(crate) fn from_id(id: u8) -> Option<CsGroup> {
+        use self::CsGroup::*;
+        [CS_GRP_INVALID, CS_GRP_JUMP, CS_GRP_CALL, CS_GRP_RET, CS_GRP_INT, CS_GRP_IRET]
+            .iter().cloned().find(|&group| group as u8 == id)
+    }
+}
+
 #[repr(C)]
 /// Architecture independent instruction detail
 pub struct InsnDetail {
@@ -159,7 +293,7 @@ pub struct InsnDetail {
     regs_read_count: u8,
     regs_write: [u8; 20],
     regs_write_count: u8,
-    groups: [CsGroup; 8],
+    groups: [u8; 8],
     groups_count: u8,
 
     pub arch_data: [u64; 185],
@@ -182,21 +316,173 @@ impl Clone for InsnDetail {
 }
 
 impl InsnDetail {
-    /// Retrieve list of groups this instruction belongs to
-    pub fn groups(&self) -> &[CsGroup] {
+    /// Registers implicitly read by this instruction
+    pub fn regs_read(&self) -> &[u8] {
+        &self.regs_read[0..self.regs_read_count as usize]
+    }
+    /// Registers implicitly written by this instruction
+    pub fn regs_write(&self) -> &[u8] {
+        &self.regs_write[0..self.regs_write_count as usize]
+    }
+    /// Retrieve the generic groups this instruction belongs to, skipping
+    /// arch-specific ones (see `group_ids`)
+    pub fn groups(&self) -> Vec<CsGroup> {
+        self.group_ids().iter().filter_map(|&id| CsGroup::from_id(id)).collect()
+    }
+    /// Raw ids of the groups this instruction belongs to, including
+    /// arch-specific ones that `CsGroup` has no variant for
+    pub fn group_ids(&self) -> &[u8] {
         &self.groups[0..self.groups_count as usize]
     }
+    /// Whether this instruction belongs to the generic `group`
+    pub fn has_group(&self, group: CsGroup) -> bool {
+        self.group_ids().contains(&(group as u8))
+    }
+    /// Each group id of this instruction with its name from `handle`
+    pub fn group_iter<'a>(&'a self, handle: &'a ::Handle) -> impl Iterator<Item = (u8, Option<&'a str>)> + 'a {
+        self.group_ids().iter().map(move |&id| (id, handle.group_name_by_id(id)))
+    }
+    /// Reinterpret `arch_data` as an architecture-specific detail struct,
+    /// catching layouts that outgrow the buffer in debug builds
+    unsafe fn arch_data_as<T>(&self) -> &T {
+        debug_assert!(mem::size_of::<T>() <= mem::size_of_val(&self.arch_data),
+                      "detail struct is larger than arch_data");
+        debug_assert!(mem::align_of::<T>() <= mem::align_of_val(&self.arch_data),
+                      "detail struct is more aligned than arch_data");
+        &*(self.arch_data.as_ptr() as *const T)
+    }
     /// Retrieve architecture-specific data for X86
     pub unsafe fn data_x86(&self) -> &detail::X86Detail {
-        mem::transmute(&self.arch_data)
+        self.arch_data_as()
     }
 
     pub unsafe fn data_arm(&self) -> &detail::ARMDetail {
-        mem::transmute(&self.arch_data)
+        self.arch_data_as()
     }
     pub unsafe fn data_ppc(&self) -> &detail::PPCDetail {
-        mem::transmute(&self.arch_data)
+        self.arch_data_as()
     }
+    pub unsafe fn data_arm64(&self) -> &detail::Arm64Detail {
+        self.arch_data_as()
+    }
+    pub unsafe fn data_sparc(&self) -> &detail::SparcDetail {
+        self.arch_data_as()
+    }
+    /// Explicit operands normalized into the architecture independent
+    /// `Operand` form.
+    ///
+    /// MIPS, SystemZ and XCore have no detail layout in this crate and
+    /// report no operands. `Handle::operands` is the safe way to call this.
+    ///
+    /// # Safety
+    ///
+    /// `arch` must be the architecture of the handle that produced this
+    /// detail; any other value reads the arch union with the wrong layout.
+    pub unsafe fn operands(&self, arch: CsArch) -> Vec<Operand> {
+        match arch {
+            CsArch::ARCH_X86 => self.data_x86().operands().iter().map(|op| match op.data() {
+                detail::X86OpData::Reg(reg) => Operand::Reg(reg),
+                detail::X86OpData::Imm(imm) => Operand::Imm(imm),
+                detail::X86OpData::Mem(mem) => Operand::Mem(MemOperand {
+                    base: mem.base,
+                    index: mem.index,
+                    scale: mem.scale,
+                    disp: mem.disp,
+                }),
+                _ => Operand::Other,
+            }).collect(),
+            CsArch::ARCH_ARM => self.data_arm().operands().iter().map(|op| match op.data() {
+                detail::ARMOpData::Reg(reg) => Operand::Reg(reg as u32),
+                detail::ARMOpData::Imm(imm) => Operand::Imm(imm as i32 as i64),
+                detail::ARMOpData::Mem(mem) => Operand::Mem(MemOperand {
+                    base: mem.base,
+                    index: mem.index,
+                    scale: mem.scale,
+                    disp: mem.disp as i64,
+                }),
+                _ => Operand::Other,
+            }).collect(),
+            CsArch::ARCH_PPC => self.data_ppc().operands().iter().map(|op| match op.data() {
+                detail::PPCOpData::Reg(reg) => Operand::Reg(reg),
+                detail::PPCOpData::Imm(imm) => Operand::Imm(imm as i32 as i64),
+                detail::PPCOpData::Mem(mem) => Operand::Mem(MemOperand {
+                    base: mem.base,
+                    index: 0,
+                    scale: 1,
+                    disp: mem.disp as i64,
+                }),
+                _ => Operand::Other,
+            }).collect(),
+            CsArch::ARCH_ARM64 => self.data_arm64().operands().iter().map(|op| match op.data() {
+                detail::Arm64OpData::Reg(reg) => Operand::Reg(reg),
+                detail::Arm64OpData::Imm(imm) => Operand::Imm(imm),
+                detail::Arm64OpData::Mem(mem) => Operand::Mem(MemOperand {
+                    base: mem.base,
+                    index: mem.index,
+                    scale: 1,
+                    disp: mem.disp as i64,
+                }),
+                _ => Operand::Other,
+            }).collect(),
+            CsArch::ARCH_SPARC => self.data_sparc().operands().iter().map(|op| match op.data() {
+                detail::SparcOpData::Reg(reg) => Operand::Reg(reg),
+                detail::SparcOpData::Imm(imm) => Operand::Imm(imm as i64),
+                detail::SparcOpData::Mem(mem) => Operand::Mem(MemOperand {
+                    base: mem.base as u32,
+                    index: mem.index as u32,
+                    scale: 1,
+                    disp: mem.disp as i64,
+                }),
+                _ => Operand::Other,
+            }).collect(),
+            _ => Vec::new(),
+        }
+    }
+    /// Number of explicit operands, read from the detail layout of `arch`.
+    ///
+    /// MIPS, SystemZ and XCore have no detail layout in this crate and
+    /// report 0. `Handle::num_operands` is the safe way to call this.
+    ///
+    /// # Safety
+    ///
+    /// `arch` must be the architecture of the handle that produced this
+    /// detail; any other value reads the arch union with the wrong layout.
+    pub unsafe fn num_operands(&self, arch: CsArch) -> usize {
+        match arch {
+            CsArch::ARCH_X86 => self.data_x86().operands().len(),
+            CsArch::ARCH_ARM => self.data_arm().operands().len(),
+            CsArch::ARCH_PPC => self.data_ppc().operands().len(),
+            CsArch::ARCH_ARM64 => self.data_arm64().operands().len(),
+            CsArch::ARCH_SPARC => self.data_sparc().operands().len(),
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Architecture independent memory operand
+pub struct MemOperand {
+    /// Base register id (0 if none)
+    pub base: u32,
+    /// Index register id (0 if none)
+    pub index: u32,
+    /// Scale applied to the index register
+    pub scale: i32,
+    /// Displacement
+    pub disp: i64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Architecture independent instruction operand
+pub enum Operand {
+    /// Register operand (architecture-specific register id)
+    Reg(u32),
+    /// Immediate operand
+    Imm(i64),
+    /// Memory operand
+    Mem(MemOperand),
+    /// Any other operand kind
+    Other,
 }
 
 impl fmt::Debug for InsnDetail {
@@ -236,10 +522,78 @@ pub mod detail {
         operands: [X86Op; 8],
     }
 
+    bitflags! {
+        #[doc="x86 instruction prefixes, see `X86Detail::prefix_flags`"]
+        pub flags X86Prefix: u32 {
+            const X86_PREFIX_LOCK = 1 << 0,
+            const X86_PREFIX_REP = 1 << 1,
+            const X86_PREFIX_REPNE = 1 << 2,
+            const X86_PREFIX_SEG_CS = 1 << 3,
+            const X86_PREFIX_SEG_SS = 1 << 4,
+            const X86_PREFIX_SEG_DS = 1 << 5,
+            const X86_PREFIX_SEG_ES = 1 << 6,
+            const X86_PREFIX_SEG_FS = 1 << 7,
+            const X86_PREFIX_SEG_GS = 1 << 8,
+            #[doc="Operand-size override (0x66)"]
+            const X86_PREFIX_OPSIZE = 1 << 9,
+            #[doc="Address-size override (0x67)"]
+            const X86_PREFIX_ADDRSIZE = 1 << 10,
+        }
+    }
+
     impl X86Detail {
         pub fn operands(&self) -> &[X86Op] {
             &self.operands[0..self.op_count as usize]
         }
+        /// Prefixes present, decoded from the four prefix slots
+        pub fn prefix_flags(&self) -> X86Prefix {
+            self.prefix.iter().fold(X86Prefix::empty(), |flags, &byte| flags | match byte {
+                0xf0 => X86_PREFIX_LOCK,
+                0xf3 => X86_PREFIX_REP,
+                0xf2 => X86_PREFIX_REPNE,
+                0x2e => X86_PREFIX_SEG_CS,
+                0x36 => X86_PREFIX_SEG_SS,
+                0x3e => X86_PREFIX_SEG_DS,
+                0x26 => X86_PREFIX_SEG_ES,
+                0x64 => X86_PREFIX_SEG_FS,
+                0x65 => X86_PREFIX_SEG_GS,
+                0x66 => X86_PREFIX_OPSIZE,
+                0x67 => X86_PREFIX_ADDRSIZE,
+                _ => X86Prefix::empty(),
+            })
+        }
+        /// Effective address size in bytes (2, 4 or 8), after any 0x67
+        /// prefix
+        pub fn address_size(&self) -> u8 {
+            self.addr_size
+        }
+        /// Effective operand size in bytes, taken from the first operand;
+        /// None for instructions without operands
+        pub fn operand_size(&self) -> Option<u8> {
+            self.operands().first().map(|op| op.size)
+        }
+        /// `mod` field (bits 7-6) of the ModR/M byte
+        pub fn modrm_mod(&self) -> u8 {
+            self.modrm >> 6
+        }
+        /// `reg` field (bits 5-3) of the ModR/M byte
+        pub fn modrm_reg(&self) -> u8 {
+            (self.modrm >> 3) & 0x7
+        }
+        /// `r/m` field (bits 2-0) of the ModR/M byte
+        pub fn modrm_rm(&self) -> u8 {
+            self.modrm & 0x7
+        }
+        /// Decoded SIB byte as (scale, index, base), or None if the
+        /// instruction has no SIB byte
+        pub fn sib(&self) -> Option<(u8, u32, u32)> {
+            // SIB only exists with 32/64-bit addressing, a memory ModR/M and r/m == 100b
+            if self.addr_size == 2 || self.modrm_mod() == 3 || self.modrm_rm() != 4 {
+                None
+            } else {
+                Some((self.sib_scale, self.sib_index, self.sib_base))
+            }
+        }
     }
 
     #[repr(C)]
@@ -258,6 +612,7 @@ pub mod detail {
         X86_OP_FP,
     }
 
+    #[repr(C)]
     #[derive(Debug)]
     /// Instruction operand for Intel x86 family
     pub struct X86Op {
@@ -265,25 +620,101 @@ pub mod detail {
         pub data: [u64; 3],
         pub size: u8,
         pub avx_bcase: u32,
-        pub avx_zero_opmask: u32,
+        pub avx_zero_opmask: bool,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// AVX-512 broadcast of a memory operand
+    pub enum X86AvxBcast {
+        /// {1to2}
+        X86_AVX_BCAST_2 = 1,
+        /// {1to4}
+        X86_AVX_BCAST_4,
+        /// {1to8}
+        X86_AVX_BCAST_8,
+        /// {1to16}
+        X86_AVX_BCAST_16,
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    /// Memory operand for Intel x86 family
+    pub struct X86OpMem {
+        /// Segment register id (0 if none)
+        pub segment: u32,
+        /// Base register id (0 if none)
+        pub base: u32,
+        /// Index register id (0 if none)
+        pub index: u32,
+        /// Scale applied to the index register
+        pub scale: i32,
+        /// Displacement
+        pub disp: i64,
+    }
+
+    impl X86OpMem {
+        /// Name of the segment override register (e.g. `fs`), or None if
+        /// the access has no segment override
+        pub fn segment_name<'a>(&self, handle: &'a ::Handle) -> Option<&'a str> {
+            match self.segment {
+                0 => None,
+                reg => handle.reg_name(reg),
+            }
+        }
     }
 
-    #[derive(Copy, Clone, Debug)]
+    #[derive(Copy, Clone, Debug, PartialEq)]
     /// Instruction operand data for Intel x86 family
     pub enum X86OpData {
+        /// Register operand
+        Reg(u32),
         /// Immediate operand
         Imm(i64),
+        /// Memory operand
+        Mem(X86OpMem),
+        /// Floating-point operand
+        Fp(f64),
         /// Other operand
         Other,
     }
 
+    /// Compare an immediate operand against a value; never equal for
+    /// other operand kinds
+    impl PartialEq<i64> for X86OpData {
+        fn eq(&self, other: &i64) -> bool {
+            match *self {
+                X86OpData::Imm(imm) => imm == *other,
+                _ => false,
+            }
+        }
+    }
+
     impl X86Op {
+        // Capstone fills cs_detail in host byte order whatever the mode's
+        // endianness, so reading the union in place is correct
         unsafe fn data_imm(&self) -> i64 {
             *mem::transmute::<&[u64; 3], &i64>(&self.data)
         }
+        /// AVX-512 broadcast applied to this operand, if any
+        pub fn avx_broadcast(&self) -> Option<X86AvxBcast> {
+            match self.avx_bcase {
+                1 => Some(X86AvxBcast::X86_AVX_BCAST_2),
+                2 => Some(X86AvxBcast::X86_AVX_BCAST_4),
+                3 => Some(X86AvxBcast::X86_AVX_BCAST_8),
+                4 => Some(X86AvxBcast::X86_AVX_BCAST_16),
+                _ => None,
+            }
+        }
+        /// Whether the AVX-512 opmask zeroes ({z}) rather than merges
+        pub fn avx_zeroing(&self) -> bool {
+            self.avx_zero_opmask
+        }
         pub fn data(&self) -> X86OpData {
             match self.ty {
+                X86OpType::X86_OP_REG => X86OpData::Reg(unsafe { *mem::transmute::<&[u64; 3], &u32>(&self.data) }),
                 X86OpType::X86_OP_IMM => X86OpData::Imm(unsafe { self.data_imm() }),
+                X86OpType::X86_OP_MEM => X86OpData::Mem(unsafe { mem::transmute(self.data) }),
+                X86OpType::X86_OP_FP => X86OpData::Fp(unsafe { *mem::transmute::<&[u64; 3], &f64>(&self.data) }),
                 _ => X86OpData::Other, // TODO this
             }
         }
@@ -298,29 +729,81 @@ pub mod detail {
         PPC_OP_MEM,
     }
 
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// Memory operand for PowerPC
+    pub struct PPCOpMem {
+        /// Base register id
+        pub base: u32,
+        /// Displacement
+        pub disp: i32,
+    }
+
     pub enum PPCOpData {
+        /// Register operand
+        Reg(u32),
         /// Immediate operand
         Imm(u32),
+        /// Memory operand
+        Mem(PPCOpMem),
         Other,
     }
 
+    #[repr(C)]
     pub struct PPCOp {
         pub ty: PPCOpType,
         pub data: [u32; 3],
     }
 
     impl PPCOp {
+        // Host byte order even in BIG_ENDIAN mode, see X86Op::data_imm
         unsafe fn data_imm(&self) -> u32 {
             *mem::transmute::<_, &u32>(&self.data)
         }
         pub fn data(&self) -> PPCOpData {
             match self.ty {
+                PPCOpType::PPC_OP_REG => PPCOpData::Reg(self.data[0]),
                 PPCOpType::PPC_OP_IMM => PPCOpData::Imm(unsafe { self.data_imm() }),
+                PPCOpType::PPC_OP_MEM => PPCOpData::Mem(PPCOpMem {
+                    base: self.data[0],
+                    disp: self.data[1] as i32,
+                }),
                 _ => PPCOpData::Other, // TODO this
             }
         }
     }
 
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// PowerPC branch condition
+    ///
+    /// Encoded as `(BI << 5) | BO`: the condition register bit tested above
+    /// the BO branch-if-true (12) or branch-if-false (4) bits.
+    pub enum PpcBc {
+        PPC_BC_LT = 12,
+        PPC_BC_LE = (1 << 5) | 4,
+        PPC_BC_EQ = (2 << 5) | 12,
+        PPC_BC_GE = 4,
+        PPC_BC_GT = (1 << 5) | 12,
+        PPC_BC_NE = (2 << 5) | 4,
+        PPC_BC_UN = (3 << 5) | 12,
+        PPC_BC_NU = (3 << 5) | 4,
+        PPC_BC_SO = (4 << 5) | 12,
+        PPC_BC_NS = (4 << 5) | 4,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// PowerPC branch hint
+    pub enum PpcBh {
+        /// Branch is likely taken (`+` suffix)
+        PPC_BH_PLUS = 1,
+        /// Branch is likely not taken (`-` suffix)
+        PPC_BH_MINUS,
+    }
+
+    /// Register id of CR0; CR1-CR7 follow consecutively (ppc_reg)
+    pub const PPC_REG_CR0: u32 = 3;
+
+    #[repr(C)]
     pub struct PPCDetail {
         pub ppc_bc: u32,
         pub ppc_bh: u32,
@@ -333,6 +816,32 @@ pub mod detail {
         pub fn operands(&self) -> &[PPCOp] {
             &self.operands[0..self.op_count as usize]
         }
+        /// Branch condition, or None for unconditional and non-branch
+        /// instructions
+        pub fn bc(&self) -> Option<PpcBc> {
+            let all = [PpcBc::PPC_BC_LT, PpcBc::PPC_BC_LE, PpcBc::PPC_BC_EQ, PpcBc::PPC_BC_GE,
+                       PpcBc::PPC_BC_GT, PpcBc::PPC_BC_NE, PpcBc::PPC_BC_UN, PpcBc::PPC_BC_NU,
+                       PpcBc::PPC_BC_SO, PpcBc::PPC_BC_NS];
+            all.iter().cloned().find(|&bc| bc as u32 == self.ppc_bc)
+        }
+        /// Indices (0-7) of the condition register fields named by the
+        /// register operands, e.g. `[1]` for `cmpw cr1, r3, r4`
+        pub fn cr_fields(&self) -> Vec<u8> {
+            self.operands().iter().filter_map(|op| match op.data() {
+                PPCOpData::Reg(reg) if reg >= PPC_REG_CR0 && reg < PPC_REG_CR0 + 8 => {
+                    Some((reg - PPC_REG_CR0) as u8)
+                }
+                _ => None,
+            }).collect()
+        }
+        /// Static branch prediction hint, or None if there is none
+        pub fn bh(&self) -> Option<PpcBh> {
+            match self.ppc_bh {
+                1 => Some(PpcBh::PPC_BH_PLUS),
+                2 => Some(PpcBh::PPC_BH_MINUS),
+                _ => None,
+            }
+        }
     }
 
     #[repr(C)]
@@ -378,6 +887,29 @@ pub mod detail {
         ARM_CC_AL             // Always (unconditional)     Always (unconditional)
     }
 
+    impl ARMCC {
+        /// The opposite condition (EQ <-> NE, ...); AL and INVALID map to themselves
+        pub fn inverse(&self) -> ARMCC {
+            match *self {
+                ARMCC::ARM_CC_EQ => ARMCC::ARM_CC_NE,
+                ARMCC::ARM_CC_NE => ARMCC::ARM_CC_EQ,
+                ARMCC::ARM_CC_HS => ARMCC::ARM_CC_LO,
+                ARMCC::ARM_CC_LO => ARMCC::ARM_CC_HS,
+                ARMCC::ARM_CC_MI => ARMCC::ARM_CC_PL,
+                ARMCC::ARM_CC_PL => ARMCC::ARM_CC_MI,
+                ARMCC::ARM_CC_VS => ARMCC::ARM_CC_VC,
+                ARMCC::ARM_CC_VC => ARMCC::ARM_CC_VS,
+                ARMCC::ARM_CC_HI => ARMCC::ARM_CC_LS,
+                ARMCC::ARM_CC_LS => ARMCC::ARM_CC_HI,
+                ARMCC::ARM_CC_GE => ARMCC::ARM_CC_LT,
+                ARMCC::ARM_CC_LT => ARMCC::ARM_CC_GE,
+                ARMCC::ARM_CC_GT => ARMCC::ARM_CC_LE,
+                ARMCC::ARM_CC_LE => ARMCC::ARM_CC_GT,
+                cc => cc,
+            }
+        }
+    }
+
     #[repr(C)]
     #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub enum ARMCPSMode {
@@ -1021,7 +1553,7 @@ pub mod detail {
     }
 
     #[repr(C)]
-    #[derive(Debug, PartialEq)]
+    #[derive(Clone, Copy, Debug, PartialEq)]
     pub struct ARMOpMem {
         pub base: u32,
         pub index: u32,
@@ -1030,7 +1562,7 @@ pub mod detail {
     }
 
     #[repr(C)]
-    #[derive(Debug, PartialEq)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub enum ARMShifter {
         ARM_SFT_INVALID = 0,
         ARM_SFT_ASR,	// shift with immediate const
@@ -1045,6 +1577,15 @@ pub mod detail {
         ARM_SFT_RRX_REG,	// shift with register
     }
 
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// Shift applied to an ARM operand
+    pub enum ArmShift {
+        /// Shift by an immediate amount (`lsl #2`)
+        Imm(ARMShifter, u32),
+        /// Shift by the amount in a register (`lsl r2`)
+        Reg(ARMShifter, ARMReg),
+    }
+
     #[repr(C)]
     #[derive(Debug, Clone)]
     pub struct ARMOp {
@@ -1056,6 +1597,15 @@ pub mod detail {
         pub subtracted: bool,
     }
 
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    /// ARM memory operand together with the sign of its index/displacement
+    pub struct ArmMemRef {
+        /// Base, index, scale and displacement
+        pub mem: ARMOpMem,
+        /// Whether the index or displacement is subtracted (e.g. `[r1, -r2]`)
+        pub subtracted: bool,
+    }
+
     #[derive(Debug, PartialEq)]
     /// Instruction operand data for ARM
     pub enum ARMOpData {
@@ -1064,6 +1614,12 @@ pub mod detail {
         Reg(ARMReg),
         Sysreg(ARMSysreg),
         Mem(ARMOpMem),
+        /// Floating-point operand
+        Fp(f64),
+        /// Coprocessor number (`p15` in `mrc p15, ...`)
+        Pimm(u32),
+        /// Coprocessor register number (`c1` in `mrc p15, #0, r0, c1, ...`)
+        Cimm(u32),
         Other,
     }
 
@@ -1071,22 +1627,79 @@ pub mod detail {
         unsafe fn data_raw(&self) -> u32 {
             *mem::transmute::<&[u64; 2], &u32>(&self.data)
         }
+        /// Memory operand with its subtraction flag, or None if this is not
+        /// a memory operand
+        pub fn mem(&self) -> Option<ArmMemRef> {
+            match self.data() {
+                ARMOpData::Mem(mem) => Some(ArmMemRef {
+                    mem: mem,
+                    subtracted: self.subtracted,
+                }),
+                _ => None,
+            }
+        }
         pub unsafe fn shifter(&self) -> ARMShifter {
             mem::transmute(self.shift_type)
         }
+        /// Shift applied to this operand, or None if it is not shifted
+        pub fn shift(&self) -> Option<ArmShift> {
+            let shifter = match self.shift_type {
+                1 => ARMShifter::ARM_SFT_ASR,
+                2 => ARMShifter::ARM_SFT_LSL,
+                3 => ARMShifter::ARM_SFT_LSR,
+                4 => ARMShifter::ARM_SFT_ROR,
+                5 => ARMShifter::ARM_SFT_RRX,
+                6 => ARMShifter::ARM_SFT_ASR_REG,
+                7 => ARMShifter::ARM_SFT_LSL_REG,
+                8 => ARMShifter::ARM_SFT_LSR_REG,
+                9 => ARMShifter::ARM_SFT_ROR_REG,
+                10 => ARMShifter::ARM_SFT_RRX_REG,
+                _ => return None,
+            };
+            // For register shifts, shift_value holds the register id
+            if self.shift_type >= ARMShifter::ARM_SFT_ASR_REG as u32 {
+                Some(ArmShift::Reg(shifter, unsafe { mem::transmute(self.shift_value) }))
+            } else {
+                Some(ArmShift::Imm(shifter, self.shift_value))
+            }
+        }
         pub fn data(&self) -> ARMOpData {
             match self.ty {
                 ARMOpType::ARM_OP_IMM => ARMOpData::Imm(unsafe { self.data_raw() }),
                 ARMOpType::ARM_OP_REG => ARMOpData::Reg(unsafe { mem::transmute( self.data_raw()) }),
                 ARMOpType::ARM_OP_SYSREG => ARMOpData::Sysreg(unsafe { mem::transmute(self.data_raw())}),
                 ARMOpType::ARM_OP_MEM => ARMOpData::Mem(unsafe { mem::transmute(self.data)}),
-                ARMOpType::ARM_OP_PIMM => ARMOpData::Imm(unsafe { self.data_raw() }),
-                ARMOpType::ARM_OP_CIMM => ARMOpData::Imm(unsafe { self.data_raw() }),
+                ARMOpType::ARM_OP_FP => ARMOpData::Fp(unsafe { *mem::transmute::<&[u64; 2], &f64>(&self.data) }),
+                ARMOpType::ARM_OP_PIMM => ARMOpData::Pimm(unsafe { self.data_raw() }),
+                ARMOpType::ARM_OP_CIMM => ARMOpData::Cimm(unsafe { self.data_raw() }),
                 _ => ARMOpData::Other, // TODO this
             }
         }
     }
 
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// ARM memory barrier option
+    pub enum ArmMemBarrier {
+        ARM_MB_INVALID = 0,
+        ARM_MB_RESERVED_0,
+        ARM_MB_OSHLD,
+        ARM_MB_OSHST,
+        ARM_MB_OSH,
+        ARM_MB_RESERVED_4,
+        ARM_MB_NSHLD,
+        ARM_MB_NSHST,
+        ARM_MB_NSH,
+        ARM_MB_RESERVED_8,
+        ARM_MB_ISHLD,
+        ARM_MB_ISHST,
+        ARM_MB_ISH,
+        ARM_MB_RESERVED_12,
+        ARM_MB_LD,
+        ARM_MB_ST,
+        ARM_MB_SY,
+    }
+
     #[repr(C)]
     pub struct ARMDetail {
         pub usermode: bool,
@@ -1104,9 +1717,75 @@ pub mod detail {
 
 
     impl ARMDetail {
+        /// Barrier option of a `dmb`/`dsb`/`isb`, or None for other
+        /// instructions
+        pub fn mem_barrier(&self) -> Option<ArmMemBarrier> {
+            use self::ArmMemBarrier::*;
+            let all = [ARM_MB_RESERVED_0, ARM_MB_OSHLD, ARM_MB_OSHST, ARM_MB_OSH, ARM_MB_RESERVED_4,
+                       ARM_MB_NSHLD, ARM_MB_NSHST, ARM_MB_NSH, ARM_MB_RESERVED_8, ARM_MB_ISHLD,
+                       ARM_MB_ISHST, ARM_MB_ISH, ARM_MB_RESERVED_12, ARM_MB_LD, ARM_MB_ST, ARM_MB_SY];
+            all.iter().cloned().find(|&mb| mb as u32 == self.mem_barrier)
+        }
         pub fn operands(&self) -> &[ARMOp] {
             &self.operands[0..self.op_count as usize]
         }
+        /// Registers transferred by a load/store-multiple (`push`, `pop`,
+        /// `ldm`, `stm` and their VFP forms), in operand order. Empty for any
+        /// other instruction.
+        pub fn register_list(&self, insn: &::Insn) -> Vec<ARMReg> {
+            let stack = [ARMInsn::ARM_INS_PUSH, ARMInsn::ARM_INS_POP,
+                         ARMInsn::ARM_INS_VPUSH, ARMInsn::ARM_INS_VPOP];
+            let with_base = [ARMInsn::ARM_INS_LDM, ARMInsn::ARM_INS_LDMDA,
+                             ARMInsn::ARM_INS_LDMDB, ARMInsn::ARM_INS_LDMIB,
+                             ARMInsn::ARM_INS_STM, ARMInsn::ARM_INS_STMDA,
+                             ARMInsn::ARM_INS_STMDB, ARMInsn::ARM_INS_STMIB,
+                             ARMInsn::ARM_INS_VLDMIA, ARMInsn::ARM_INS_VLDMDB,
+                             ARMInsn::ARM_INS_VSTMIA, ARMInsn::ARM_INS_VSTMDB];
+            // ldm/stm carry the base register as their first operand
+            let skip = if stack.iter().any(|&id| id as u32 == insn.id) {
+                0
+            } else if with_base.iter().any(|&id| id as u32 == insn.id) {
+                1
+            } else {
+                return Vec::new();
+            };
+            self.operands().iter().skip(skip).filter_map(|op| match op.data() {
+                ARMOpData::Reg(reg) => Some(reg),
+                _ => None,
+            }).collect()
+        }
+        /// Register pair of a doubleword transfer (e.g. `ldrd r0, r1, [r2]`):
+        /// an even-numbered register followed by the next one
+        pub fn reg_pair(&self, insn: &::Insn) -> Option<(ARMReg, ARMReg)> {
+            let doubleword = [ARMInsn::ARM_INS_LDRD, ARMInsn::ARM_INS_STRD,
+                              ARMInsn::ARM_INS_LDREXD, ARMInsn::ARM_INS_STREXD,
+                              ARMInsn::ARM_INS_LDAEXD, ARMInsn::ARM_INS_STLEXD];
+            if !doubleword.iter().any(|&id| id as u32 == insn.id) {
+                return None;
+            }
+            // Exclusive stores lead with the status register: strexd r0, r2, r3, [r1]
+            let skip = match insn.id {
+                id if id == ARMInsn::ARM_INS_STREXD as u32 || id == ARMInsn::ARM_INS_STLEXD as u32 => 1,
+                _ => 0,
+            };
+            let ops = self.operands();
+            if ops.len() < skip + 2 {
+                return None;
+            }
+            let ops = &ops[skip..];
+            match (ops[0].data(), ops[1].data()) {
+                (ARMOpData::Reg(first), ARMOpData::Reg(second)) => {
+                    let r0 = ARMReg::ARM_REG_R0 as u32;
+                    let (lo, hi) = (first as u32, second as u32);
+                    if lo >= r0 && hi <= ARMReg::ARM_REG_R12 as u32 && (lo - r0) % 2 == 0 && hi == lo + 1 {
+                        Some((first, second))
+                    } else {
+                        None
+                    }
+                },
+                _ => None,
+            }
+        }
     }
 
     impl fmt::Debug for ARMDetail {
@@ -1127,6 +1806,331 @@ pub mod detail {
         }
     }
 
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// Instruction operand type for ARM64
+    pub enum Arm64OpType {
+        ARM64_OP_INVALID = 0,
+        ARM64_OP_REG,
+        ARM64_OP_IMM,
+        ARM64_OP_MEM,
+        ARM64_OP_FP,
+        ARM64_OP_CIMM = 64,
+        ARM64_OP_REG_MRS,
+        ARM64_OP_REG_MSR,
+        ARM64_OP_PSTATE,
+        ARM64_OP_SYS,
+        ARM64_OP_PREFETCH,
+        ARM64_OP_BARRIER,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// ARM64 operand shift type
+    pub enum Arm64Shifter {
+        ARM64_SFT_INVALID = 0,
+        ARM64_SFT_LSL,
+        ARM64_SFT_MSL,
+        ARM64_SFT_LSR,
+        ARM64_SFT_ASR,
+        ARM64_SFT_ROR,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// ARM64 register extend type
+    pub enum Arm64Extender {
+        ARM64_EXT_INVALID = 0,
+        ARM64_EXT_UXTB,
+        ARM64_EXT_UXTH,
+        ARM64_EXT_UXTW,
+        ARM64_EXT_UXTX,
+        ARM64_EXT_SXTB,
+        ARM64_EXT_SXTH,
+        ARM64_EXT_SXTW,
+        ARM64_EXT_SXTX,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// ARM64 condition code
+    pub enum Arm64Cc {
+        ARM64_CC_INVALID = 0,
+        ARM64_CC_EQ,          // Equal
+        ARM64_CC_NE,          // Not equal
+        ARM64_CC_HS,          // Unsigned higher or same
+        ARM64_CC_LO,          // Unsigned lower
+        ARM64_CC_MI,          // Minus, negative
+        ARM64_CC_PL,          // Plus, positive or zero
+        ARM64_CC_VS,          // Overflow
+        ARM64_CC_VC,          // No overflow
+        ARM64_CC_HI,          // Unsigned higher
+        ARM64_CC_LS,          // Unsigned lower or same
+        ARM64_CC_GE,          // Greater than or equal
+        ARM64_CC_LT,          // Less than
+        ARM64_CC_GT,          // Greater than
+        ARM64_CC_LE,          // Less than or equal
+        ARM64_CC_AL,          // Always (unconditional)
+        ARM64_CC_NV,          // Always (unconditional), reserved encoding
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// Memory operand for ARM64
+    pub struct Arm64OpMem {
+        /// Base register id (0 if none)
+        pub base: u32,
+        /// Index register id (0 if none)
+        pub index: u32,
+        /// Displacement
+        pub disp: i32,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    /// Instruction operand data for ARM64
+    pub enum Arm64OpData {
+        /// Register operand
+        Reg(u32),
+        /// Immediate operand
+        Imm(i64),
+        /// Memory operand
+        Mem(Arm64OpMem),
+        /// Floating-point operand
+        Fp(f64),
+        /// Other operand
+        Other,
+    }
+
+    #[repr(C)]
+    #[derive(Debug)]
+    /// Instruction operand for ARM64
+    pub struct Arm64Op {
+        pub vector_index: i32,
+        pub vas: u32,
+        pub vess: u32,
+        pub shift_type: u32,
+        pub shift_value: u32,
+        pub ext: u32,
+        pub ty: Arm64OpType,
+        pub data: [u64; 2],
+    }
+
+    impl Arm64Op {
+        pub fn data(&self) -> Arm64OpData {
+            match self.ty {
+                Arm64OpType::ARM64_OP_REG => Arm64OpData::Reg(self.data[0] as u32),
+                Arm64OpType::ARM64_OP_IMM | Arm64OpType::ARM64_OP_CIMM => Arm64OpData::Imm(self.data[0] as i64),
+                Arm64OpType::ARM64_OP_MEM => Arm64OpData::Mem(Arm64OpMem {
+                    base: self.data[0] as u32,
+                    index: (self.data[0] >> 32) as u32,
+                    disp: self.data[1] as u32 as i32,
+                }),
+                Arm64OpType::ARM64_OP_FP => Arm64OpData::Fp(unsafe { mem::transmute(self.data[0]) }),
+                _ => Arm64OpData::Other,
+            }
+        }
+        /// Shift applied to this operand and its amount, or None if it is
+        /// not shifted
+        pub fn shift(&self) -> Option<(Arm64Shifter, u32)> {
+            let shifter = match self.shift_type {
+                1 => Arm64Shifter::ARM64_SFT_LSL,
+                2 => Arm64Shifter::ARM64_SFT_MSL,
+                3 => Arm64Shifter::ARM64_SFT_LSR,
+                4 => Arm64Shifter::ARM64_SFT_ASR,
+                5 => Arm64Shifter::ARM64_SFT_ROR,
+                _ => return None,
+            };
+            Some((shifter, self.shift_value))
+        }
+        /// Extend applied to this register operand and the left shift that
+        /// follows it (`sxtw #2` gives `(ARM64_EXT_SXTW, 2)`), or None
+        pub fn extend(&self) -> Option<(Arm64Extender, u32)> {
+            let ext = match self.ext {
+                1 => Arm64Extender::ARM64_EXT_UXTB,
+                2 => Arm64Extender::ARM64_EXT_UXTH,
+                3 => Arm64Extender::ARM64_EXT_UXTW,
+                4 => Arm64Extender::ARM64_EXT_UXTX,
+                5 => Arm64Extender::ARM64_EXT_SXTB,
+                6 => Arm64Extender::ARM64_EXT_SXTH,
+                7 => Arm64Extender::ARM64_EXT_SXTW,
+                8 => Arm64Extender::ARM64_EXT_SXTX,
+                _ => return None,
+            };
+            // Capstone reports the amount as an LSL shift on the same operand
+            let amount = match self.shift() {
+                Some((Arm64Shifter::ARM64_SFT_LSL, amount)) => amount,
+                _ => 0,
+            };
+            Some((ext, amount))
+        }
+    }
+
+    #[repr(C)]
+    #[derive(Debug)]
+    /// Platform-specific instruction detail for ARM64
+    pub struct Arm64Detail {
+        pub cc: Arm64Cc,
+        pub update_flags: bool,
+        pub writeback: bool,
+        op_count: u8,
+        operands: [Arm64Op; 8],
+    }
+
+    impl Arm64Detail {
+        pub fn operands(&self) -> &[Arm64Op] {
+            &self.operands[0..self.op_count as usize]
+        }
+        /// Condition the instruction executes under, or None if it is
+        /// unconditional
+        pub fn condition(&self) -> Option<Arm64Cc> {
+            match self.cc {
+                Arm64Cc::ARM64_CC_INVALID | Arm64Cc::ARM64_CC_AL | Arm64Cc::ARM64_CC_NV => None,
+                cc => Some(cc),
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// ARM64 pointer authentication and branch target identification
+    /// instructions in the HINT space, see `Handle::arm64_hint`
+    pub enum Arm64Hint {
+        XPACLRI = 7,
+        PACIA1716 = 8,
+        PACIB1716 = 10,
+        AUTIA1716 = 12,
+        AUTIB1716 = 14,
+        PACIAZ = 24,
+        PACIASP = 25,
+        PACIBZ = 26,
+        PACIBSP = 27,
+        AUTIAZ = 28,
+        AUTIASP = 29,
+        AUTIBZ = 30,
+        AUTIBSP = 31,
+        BTI = 32,
+        BTI_C = 34,
+        BTI_J = 36,
+        BTI_JC = 38,
+    }
+
+    bitflags! {
+        #[repr(C)]
+        #[doc="SPARC branch hint bits"]
+        pub flags SparcHint: u32 {
+            #[doc="Annul the delay slot (`,a`)"]
+            const SPARC_HINT_A = 1 << 0,
+            #[doc="Predict taken (`,pt`)"]
+            const SPARC_HINT_PT = 1 << 1,
+            #[doc="Predict not taken (`,pn`)"]
+            const SPARC_HINT_PN = 1 << 2,
+        }
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum SparcOpType {
+        SPARC_OP_INVALID = 0,
+        SPARC_OP_REG,
+        SPARC_OP_IMM,
+        SPARC_OP_MEM,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// Memory operand for SPARC
+    pub struct SparcOpMem {
+        /// Base register id
+        pub base: u8,
+        /// Index register id
+        pub index: u8,
+        /// Displacement
+        pub disp: i32,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// Instruction operand data for SPARC
+    pub enum SparcOpData {
+        /// Register operand
+        Reg(u32),
+        /// Immediate operand
+        Imm(i32),
+        /// Memory operand
+        Mem(SparcOpMem),
+        /// Other operand
+        Other,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// SPARC register window class
+    pub enum SparcRegClass {
+        /// `%g0`-`%g7`
+        Global,
+        /// `%o0`-`%o7` (including `%sp`)
+        Out,
+        /// `%l0`-`%l7`
+        Local,
+        /// `%i0`-`%i7` (including `%fp`)
+        In,
+    }
+
+    #[repr(C)]
+    #[derive(Debug)]
+    /// Instruction operand for SPARC
+    pub struct SparcOp {
+        pub ty: SparcOpType,
+        pub data: [u32; 2],
+    }
+
+    impl SparcOp {
+        pub fn data(&self) -> SparcOpData {
+            match self.ty {
+                SparcOpType::SPARC_OP_REG => SparcOpData::Reg(self.data[0]),
+                SparcOpType::SPARC_OP_IMM => SparcOpData::Imm(self.data[0] as i32),
+                SparcOpType::SPARC_OP_MEM => SparcOpData::Mem(unsafe { mem::transmute(self.data) }),
+                _ => SparcOpData::Other,
+            }
+        }
+        /// Register window class of a register operand, or None for other
+        /// operands and non-window registers
+        pub fn reg_class(&self, handle: &::Handle) -> Option<SparcRegClass> {
+            let reg = match self.data() {
+                SparcOpData::Reg(reg) => reg,
+                _ => return None,
+            };
+            match handle.reg_name(reg) {
+                Some("sp") => Some(SparcRegClass::Out),
+                Some("fp") => Some(SparcRegClass::In),
+                Some(name) if name.len() == 2 => match name.as_bytes()[0] {
+                    b'g' => Some(SparcRegClass::Global),
+                    b'o' => Some(SparcRegClass::Out),
+                    b'l' => Some(SparcRegClass::Local),
+                    b'i' => Some(SparcRegClass::In),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+    }
+
+    #[repr(C)]
+    /// Platform-specific instruction detail for SPARC
+    pub struct SparcDetail {
+        pub cc: u32,
+        hint: u32,
+        op_count: u8,
+        operands: [SparcOp; 4],
+    }
+
+    impl SparcDetail {
+        pub fn operands(&self) -> &[SparcOp] {
+            &self.operands[0..self.op_count as usize]
+        }
+        /// Annul and branch prediction hints
+        pub fn hint(&self) -> SparcHint {
+            SparcHint::from_bits_truncate(self.hint)
+        }
+    }
+
 }
 
 #[repr(C)]
@@ -1150,6 +2154,29 @@ impl Insn {
     pub fn size(&self) -> u16 {
         self.size
     }
+    /// Address of the instruction that follows this one (fall-through)
+    pub fn next_address(&self) -> u64 {
+        self.address + self.size as u64
+    }
+    /// Machine bytes of this instruction (all prefixes included)
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes[..self.size as usize]
+    }
+    /// Offset of this instruction from `module_base` (0 if it lies below it)
+    pub fn file_offset(&self, module_base: u64) -> u64 {
+        self.address.saturating_sub(module_base)
+    }
+    /// Raw bytes of the mnemonic, for lossy conversion when they aren't
+    /// valid UTF-8
+    pub fn mnemonic_bytes(&self) -> &[u8] {
+        unsafe { std::ffi::CStr::from_ptr(self.mnemonic.as_ptr()) }.to_bytes()
+    }
+
+    /// Raw bytes of the operand string, see `mnemonic_bytes`
+    pub fn op_str_bytes(&self) -> &[u8] {
+        unsafe { std::ffi::CStr::from_ptr(self.op_str.as_ptr()) }.to_bytes()
+    }
+
     /// Instruction mnemonic (e.g., 'mov', 'push')
     pub fn mnemonic(&self) -> Option<&str> {
         let cstr = unsafe { std::ffi::CStr::from_ptr(self.mnemonic.as_ptr()) };
@@ -1161,6 +2188,56 @@ impl Insn {
         let cstr = unsafe { std::ffi::CStr::from_ptr(self.op_str.as_ptr()) };
         str::from_utf8(cstr.to_bytes()).ok()
     }
+    /// Owned copies of the mnemonic and operand string, for keeping them
+    /// past the instruction's lifetime (e.g. out of a `walk_insts` callback)
+    pub fn text(&self) -> (String, String) {
+        (self.mnemonic().unwrap_or("").to_string(), self.op_str().unwrap_or("").to_string())
+    }
+    /// Whether this instruction is data skipped over in SKIPDATA mode
+    pub fn is_skipdata(&self) -> bool {
+        self.id == 0
+    }
+    /// Render the instruction as GNU `as` accepts it: AT&T syntax on x86
+    /// (re-decoding with an AT&T handle if `handle` uses another syntax),
+    /// and Capstone's normal output elsewhere
+    pub fn to_gas(&self, handle: &::Handle) -> String {
+        let join = |mnemonic: &str, op_str: &str| match op_str {
+            "" => mnemonic.to_string(),
+            _ => format!("{} {}", mnemonic, op_str),
+        };
+        if handle.arch() == CsArch::ARCH_X86 && handle.syntax() != optval::CS_OPT_SYNTAX_ATT {
+            let att = ::HandleBuilder::new(handle.arch(), handle.mode())
+                .syntax(optval::CS_OPT_SYNTAX_ATT)
+                .build();
+            if let Ok(att) = att {
+                if let Ok(insns) = att.disasm(self.bytes(), self.address, 1) {
+                    if let Some(insn) = insns.first() {
+                        return join(insn.mnemonic().unwrap_or(""), insn.op_str().unwrap_or(""));
+                    }
+                }
+            }
+        }
+        join(self.mnemonic().unwrap_or(""), self.op_str().unwrap_or(""))
+    }
+    /// The raw bytes of skipped data, or None if this is a real instruction
+    pub fn skipdata_bytes(&self) -> Option<&[u8]> {
+        match self.is_skipdata() {
+            true => Some(self.bytes()),
+            false => None,
+        }
+    }
+    /// Architecture-independent instruction detail, with the reason it is
+    /// unavailable: `CS_ERR_DETAIL` if `handle` has detail off,
+    /// `CS_ERR_SKIPDATA` if this is skipped data
+    pub fn try_detail(&self, handle: &::Handle) -> Result<&InsnDetail, ::CsError> {
+        if !handle.detail_enabled() {
+            return Err(::CsError::CS_ERR_DETAIL);
+        }
+        if self.is_skipdata() {
+            return Err(::CsError::CS_ERR_SKIPDATA);
+        }
+        self.detail().ok_or(::CsError::CS_ERR_DETAIL)
+    }
     /// Architecture-independent instruction detail
     pub fn detail(&self) -> Option<&InsnDetail> {
         // ID 0 is skipdata
@@ -1174,6 +2251,23 @@ impl Insn {
     }
 }
 
+// Instructions are identified by where they are and what they encode; the
+// detail pointer is deliberately ignored
+impl PartialEq for Insn {
+    fn eq(&self, other: &Insn) -> bool {
+        self.address == other.address && self.bytes() == other.bytes()
+    }
+}
+
+impl Eq for Insn {}
+
+impl Hash for Insn {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.address.hash(state);
+        self.bytes().hash(state);
+    }
+}
+
 impl fmt::Debug for Insn {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         fmt.debug_struct("Insn")
@@ -1187,7 +2281,7 @@ impl fmt::Debug for Insn {
 
 pub fn set_opt(csh: CsHandle, opt: CsOptType, val: CsOptValue) -> Result<(), ::CsError> {
     unsafe {
-        match cs_option(csh, opt, val.0) {
+        match cs_option(csh, opt, val.0 as libc::size_t) {
             ::CsError::CS_ERR_OK => Ok(()),
             e => Err(e),
         }
@@ -1195,8 +2289,12 @@ pub fn set_opt(csh: CsHandle, opt: CsOptType, val: CsOptValue) -> Result<(), ::C
 }
 
 pub fn group_name<'a>(csh: CsHandle, group: CsGroup) -> Option<&'a str> {
+    group_name_by_id(csh, group as u8)
+}
+
+pub fn group_name_by_id<'a>(csh: CsHandle, group: u8) -> Option<&'a str> {
     unsafe {
-        let name = cs_group_name(csh, group);
+        let name = cs_group_name(csh, group as libc::c_uint);
         if name.is_null() {
             None
         } else {
@@ -1208,11 +2306,103 @@ pub fn group_name<'a>(csh: CsHandle, group: CsGroup) -> Option<&'a str> {
     }
 }
 
+pub fn reg_name<'a>(csh: CsHandle, reg: u32) -> Option<&'a str> {
+    unsafe {
+        let name = cs_reg_name(csh, reg as libc::c_uint);
+        if name.is_null() {
+            None
+        } else {
+            std::ffi::CStr::from_ptr(name).to_str().ok()
+        }
+    }
+}
+
+/// Capstone major version whose `cs_detail` layout (`arch_data` size and the
+/// per-arch structs) this crate is written against
+pub const CS_API_MAJOR: u32 = 3;
+
+/// Version of the linked Capstone library, as (major, minor)
+pub fn version() -> (u32, u32) {
+    let mut major = 0;
+    let mut minor = 0;
+    unsafe { cs_version(&mut major, &mut minor) };
+    (major as u32, minor as u32)
+}
+
+/// Sizes and offsets (in bytes) this crate assumes for the C detail
+/// structs, from `detail_layout_info`
+///
+/// Compare against `sizeof`/`offsetof` in C when operand data looks like
+/// garbage: a mismatch means the linked Capstone has a different layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DetailLayout {
+    /// `sizeof(cs_insn)`
+    pub insn: usize,
+    /// `sizeof(cs_detail)`
+    pub insn_detail: usize,
+    /// `offsetof(cs_detail, x86)`, where the arch-specific union starts
+    pub arch_data_offset: usize,
+    /// `sizeof(cs_x86)`
+    pub x86: usize,
+    /// `sizeof(cs_arm)`
+    pub arm: usize,
+    /// `sizeof(cs_arm64)`
+    pub arm64: usize,
+    /// `sizeof(cs_ppc)`
+    pub ppc: usize,
+    /// `sizeof(cs_sparc)`
+    pub sparc: usize,
+}
+
+/// Report the detail struct layout the crate was compiled with
+pub fn detail_layout_info() -> DetailLayout {
+    let detail: InsnDetail = unsafe { mem::zeroed() };
+    DetailLayout {
+        insn: mem::size_of::<Insn>(),
+        insn_detail: mem::size_of::<InsnDetail>(),
+        arch_data_offset: &detail.arch_data as *const _ as usize - &detail as *const _ as usize,
+        x86: mem::size_of::<detail::X86Detail>(),
+        arm: mem::size_of::<detail::ARMDetail>(),
+        arm64: mem::size_of::<detail::Arm64Detail>(),
+        ppc: mem::size_of::<detail::PPCDetail>(),
+        sparc: mem::size_of::<detail::SparcDetail>(),
+    }
+}
+
+/// What the linked Capstone library was built with, from `capabilities`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Built in "diet" mode (no mnemonic/operand strings, no detail)
+    pub diet: bool,
+    /// Built with the reduced x86 instruction set
+    pub x86_reduce: bool,
+    /// Architectures compiled in
+    pub archs: Vec<CsArch>,
+}
+
+/// Query the linked Capstone library's build options and architectures
+pub fn capabilities() -> Capabilities {
+    // CS_SUPPORT_DIET and CS_SUPPORT_X86_REDUCE follow CS_ARCH_ALL
+    let all = CsArch::ARCH_ALL as libc::c_int;
+    let archs = [CsArch::ARCH_ARM, CsArch::ARCH_ARM64, CsArch::ARCH_MIPS, CsArch::ARCH_X86,
+                 CsArch::ARCH_PPC, CsArch::ARCH_SPARC, CsArch::ARCH_SYSZ, CsArch::ARCH_XCORE];
+    unsafe {
+        Capabilities {
+            diet: cs_support(all + 1),
+            x86_reduce: cs_support(all + 2),
+            archs: archs.iter().cloned().filter(|&arch| cs_support(arch as libc::c_int)).collect(),
+        }
+    }
+}
+
 pub fn new_csh(arch: CsArch, mode: CsMode) -> Result<::Handle, ::CsError> {
     let mut handle = 0;
+    // Hold the lock so a CS_OPT_MEM change can't race with opening a handle
+    let mut state = ::mem::state();
     let err = unsafe { cs_open(arch, mode, &mut handle) };
     if err == ::CsError::CS_ERR_OK {
-        Ok(::Handle::from(handle))
+        state.open_handles += 1;
+        Ok(::Handle::from_parts(handle, arch, mode))
     } else {
         Err(err)
     }
@@ -1229,8 +2419,11 @@ extern "C" {
     pub fn cs_disasm_iter(handle: CsHandle, code: *mut *const u8, code_size: *mut libc::size_t,
                           address: *mut u64, insn: *const Insn) -> bool;
     pub fn cs_free(insn: *const Insn, count: libc::size_t);
-    pub fn cs_option(handle: CsHandle, opt: CsOptType, val: u32) -> ::CsError;
+    pub fn cs_option(handle: CsHandle, opt: CsOptType, val: libc::size_t) -> ::CsError;
     pub fn cs_errno(handle: CsHandle) -> ::CsError;
-    pub fn cs_group_name(handle: CsHandle, name: CsGroup) -> *const libc::c_char;
+    pub fn cs_group_name(handle: CsHandle, group_id: libc::c_uint) -> *const libc::c_char;
+    pub fn cs_reg_name(handle: CsHandle, reg_id: libc::c_uint) -> *const libc::c_char;
     pub fn cs_strerror(code: ::CsError) -> *const libc::c_char;
+    pub fn cs_version(major: *mut libc::c_int, minor: *mut libc::c_int) -> libc::c_uint;
+    pub fn cs_support(query: libc::c_int) -> bool;
 }
@@ -1,23 +1,131 @@
 use libc;
 use std;
 use std::ptr;
+use std::collections::HashMap;
 use ffi;
 
 /// Handle to Capstone Engine instance
-pub struct Handle(ffi::CsHandle);
+pub struct Handle {
+    csh: ffi::CsHandle,
+    arch: ffi::CsArch,
+    mode: ffi::CsMode,
+    detail: bool,
+    skipdata: bool,
+    skipdata_bytes: Option<usize>,
+    syntax: ffi::CsOptValue,
+}
 
 impl Handle {
-    /// Disassemble all instructions into a buffer
+    /// Disassemble up to `count` instructions (all of them if `count` is 0)
+    /// into a buffer
+    ///
+    /// The result holds only the instructions actually decoded, which may be
+    /// fewer than `count` if the code runs out or hits undecodable bytes.
     pub fn disasm(&self, code: &[u8], addr: u64, count: isize) -> Result<Instructions, ::CsError> {
         let mut ptr: *const ffi::Insn = ptr::null();
-        let insn_count = unsafe { ffi::cs_disasm(self.0, code.as_ptr(), code.len() as libc::size_t,
+        let insn_count = unsafe { ffi::cs_disasm(self.csh, code.as_ptr(), code.len() as libc::size_t,
                                             addr, count as libc::size_t, &mut ptr) };
         if insn_count == 0 {
-            let err = unsafe { ffi::cs_errno(self.0) };
+            let err = unsafe { ffi::cs_errno(self.csh) };
             return Err(err)
         }
 
-        Ok(Instructions::from_parts(ptr, count as usize))
+        // The engine may decode fewer instructions than requested (or all of
+        // them when count is 0), so only the reported number are valid.
+        Ok(Instructions::from_parts(ptr, insn_count as usize))
+    }
+
+    /// Disassemble all of `code`, loaded at `addr` (`disasm` with a count
+    /// of 0)
+    pub fn disasm_at(&self, code: &[u8], addr: u64) -> Result<Instructions, ::CsError> {
+        self.disasm(code, addr, 0)
+    }
+
+    /// Disassemble all of `code`, requiring every byte to decode
+    ///
+    /// Returns `CS_ERR_SKIPDATA` if decoding stops before the end of the
+    /// buffer (junk or a truncated instruction), or if SKIPDATA is on and
+    /// some bytes were only skipped over as data.
+    pub fn disasm_exact(&self, code: &[u8], addr: u64) -> Result<Instructions, ::CsError> {
+        let insns = try!(self.disasm(code, addr, 0));
+        let decoded: usize = insns.iter().map(|insn| insn.size() as usize).sum();
+        if decoded != code.len() || insns.iter().any(|insn| insn.is_skipdata()) {
+            return Err(::CsError::CS_ERR_SKIPDATA);
+        }
+        Ok(insns)
+    }
+
+    /// Disassemble at most `max_insns` instructions, so untrusted input
+    /// can't make the engine allocate an unbounded instruction array
+    ///
+    /// A cap of 0 yields an empty result rather than meaning "all".
+    pub fn disasm_bounded(&self, code: &[u8], addr: u64, max_insns: usize) -> Result<Instructions, ::CsError> {
+        if max_insns == 0 {
+            return Ok(Instructions::from_parts(ptr::null(), 0));
+        }
+        self.disasm(code, addr, max_insns as isize)
+    }
+
+    /// Disassemble the instructions starting in `[start_addr, end_addr)`,
+    /// where `code` is loaded at `start_addr`
+    ///
+    /// The last instruction may extend past `end_addr`. Returns no
+    /// instructions if none could be decoded in the range.
+    pub fn disasm_range(&self, code: &[u8], start_addr: u64, end_addr: u64) -> Result<Instructions, ::CsError> {
+        if end_addr <= start_addr {
+            return Ok(Instructions::from_parts(ptr::null(), 0));
+        }
+        // Leave room for an instruction starting just before end_addr (x86
+        // instructions are at most 15 bytes), so only the range is decoded
+        let span = std::cmp::min((end_addr - start_addr) as usize, code.len());
+        let code = &code[..std::cmp::min(code.len(), span.saturating_add(15))];
+        let mut insns = match self.disasm(code, start_addr, 0) {
+            Ok(insns) => insns,
+            // Nothing decodable at start_addr
+            Err(::CsError::CS_ERR_OK) => return Ok(Instructions::from_parts(ptr::null(), 0)),
+            Err(err) => return Err(err),
+        };
+        let in_range = insns.iter().take_while(|insn| insn.address < end_addr).count();
+        insns.truncate(in_range);
+        Ok(insns)
+    }
+
+    /// Disassemble all instructions of an object file section loaded at
+    /// `section_addr` (see `mode_from_elf_machine` for picking arch and mode)
+    #[cfg(feature = "object")]
+    pub fn disasm_section(&self, section_data: &[u8], section_addr: u64) -> Result<Instructions, ::CsError> {
+        self.disasm(section_data, section_addr, 0)
+    }
+
+    /// Disassemble all instructions, pairing each with its index and its
+    /// byte offset from the start of `code`
+    pub fn disasm_enumerated(&self, code: &[u8], addr: u64) -> Result<Vec<(usize, u64, ::OwnedInsn)>, ::CsError> {
+        let insns = try!(self.disasm(code, addr, 0));
+        Ok(insns.iter().enumerate()
+           .map(|(i, insn)| (i, insn.address - addr, ::OwnedInsn::from(insn)))
+           .collect())
+    }
+
+    /// Disassemble all instructions into a result that also owns `code`,
+    /// so it can be stored and re-decoded later
+    pub fn disasm_owned_bytes(&self, code: Vec<u8>, addr: u64) -> Result<::OwnedDisasm, ::CsError> {
+        let insns = try!(self.disasm(&code, addr, 0)).iter().map(::OwnedInsn::from).collect();
+        Ok(::OwnedDisasm {
+            addr: addr,
+            code: code,
+            insns: insns,
+        })
+    }
+
+    /// Disassemble all instructions, serving repeated (code, addr) pairs
+    /// for this handle's arch and mode from `cache` instead of the engine
+    pub fn disasm_cached(&self, cache: &mut ::DisasmCache, code: &[u8], addr: u64) -> Result<Vec<::OwnedInsn>, ::CsError> {
+        if let Some(insns) = cache.get(self.arch, self.mode, code, addr) {
+            return Ok(insns);
+        }
+        let insns: Vec<::OwnedInsn> = try!(self.disasm(code, addr, 0)).iter().map(::OwnedInsn::from).collect();
+        cache.insert(self.arch, self.mode, code, addr, insns.clone());
+        Ok(insns)
     }
 
     #[must_use]
@@ -27,42 +135,863 @@ impl Handle {
         let mut code_ptr = code.as_ptr();
         let mut code_sz = code.len();
         unsafe {
-            let insn = ffi::cs_malloc(self.0);
-            if insn.is_null() {
-                return Err(ffi::cs_errno(self.0));
-            }
-            while ffi::cs_disasm_iter(self.0, &mut code_ptr, &mut code_sz, &mut addr, insn) {
+            let insn = try!(self.malloc_insn());
+            while ffi::cs_disasm_iter(self.csh, &mut code_ptr, &mut code_sz, &mut addr, insn) {
                 f(&*insn);
             }
             ffi::cs_free(insn, 1);
         }
         Ok(())
     }
+    /// Start decoding `code` one instruction at a time; the returned cursor
+    /// keeps its byte position, so decoding can stop and pick up later
+    pub fn iter_disasm<'a>(&'a self, code: &'a [u8], addr: u64) -> Result<DecodeCursor<'a>, ::CsError> {
+        let insn = try!(self.malloc_insn());
+        Ok(DecodeCursor {
+            handle: self,
+            insn: insn,
+            code_ptr: code.as_ptr(),
+            code_sz: code.len(),
+            addr: addr,
+            _code: std::marker::PhantomData,
+        })
+    }
+    /// Disassemble all of `code` linearly, turning each byte that can't be
+    /// decoded into a one-byte `(bad)` instruction and carrying on
+    ///
+    /// The `(bad)` entries have id `OwnedInsn::BAD_ID` (see
+    /// `OwnedInsn::is_bad`), so they can't be mistaken for SKIPDATA data.
+    pub fn disasm_linear(&self, code: &[u8], mut addr: u64) -> Result<Vec<::OwnedInsn>, ::CsError> {
+        let mut out = Vec::new();
+        let mut code_ptr = code.as_ptr();
+        let mut code_sz = code.len();
+        unsafe {
+            let insn = try!(self.malloc_insn());
+            while code_sz > 0 {
+                if ffi::cs_disasm_iter(self.csh, &mut code_ptr, &mut code_sz, &mut addr, insn) {
+                    out.push(::OwnedInsn::from(&*insn));
+                } else {
+                    out.push(::OwnedInsn {
+                        id: ::OwnedInsn::BAD_ID,
+                        address: addr,
+                        bytes: vec![*code_ptr],
+                        mnemonic: "(bad)".to_string(),
+                        op_str: String::new(),
+                    });
+                    code_ptr = code_ptr.offset(1);
+                    code_sz -= 1;
+                    addr += 1;
+                }
+            }
+            ffi::cs_free(insn, 1);
+        }
+        Ok(out)
+    }
+    #[must_use]
+    /// Like `walk_insts`, but stops as soon as `f` returns false
+    pub fn walk_insts_while<F>(&self, code: &[u8], mut addr: u64, mut f: F) -> Result<(), ::CsError> where F: FnMut(&ffi::Insn) -> bool {
+        let mut code_ptr = code.as_ptr();
+        let mut code_sz = code.len();
+        unsafe {
+            let insn = try!(self.malloc_insn());
+            while ffi::cs_disasm_iter(self.csh, &mut code_ptr, &mut code_sz, &mut addr, insn) {
+                if !f(&*insn) {
+                    break;
+                }
+            }
+            ffi::cs_free(insn, 1);
+        }
+        Ok(())
+    }
+    /// Disassemble all of `code` into owned instructions paired with owned
+    /// copies of their detail (None with detail off, or for skipped data)
+    pub fn disasm_with_detail(&self, code: &[u8], addr: u64)
+            -> Result<Vec<(::OwnedInsn, Option<::OwnedDetail>)>, ::CsError> {
+        let mut out = Vec::new();
+        try!(self.walk_insts(code, addr, |insn| {
+            out.push((::OwnedInsn::from(insn), insn.detail().map(::OwnedDetail::from)));
+        }));
+        Ok(out)
+    }
+
+    /// Disassemble a function starting at `code[0]`, stopping after the
+    /// first return (`CS_GRP_RET`) or after `max_bytes` bytes
+    ///
+    /// Requires detail (`CS_ERR_DETAIL` otherwise), since return mnemonics
+    /// vary with arch and syntax (`retq`, `ret 8`, `bx lr`, ...).
+    pub fn disasm_function(&self, code: &[u8], addr: u64, max_bytes: usize) -> Result<Vec<::OwnedInsn>, ::CsError> {
+        if !self.detail {
+            return Err(::CsError::CS_ERR_DETAIL);
+        }
+        let code = &code[..std::cmp::min(code.len(), max_bytes)];
+        let mut out = Vec::new();
+        try!(self.walk_insts_while(code, addr, |insn| {
+            out.push(::OwnedInsn::from(insn));
+            !insn.detail().map_or(false, |detail| detail.has_group(ffi::CsGroup::CS_GRP_RET))
+        }));
+        Ok(out)
+    }
+
+    /// Disassemble `code` one instruction at a time, keeping only those
+    /// matching `pred`
+    pub fn disasm_filter<P>(&self, code: &[u8], addr: u64, pred: P) -> Result<Vec<::OwnedInsn>, ::CsError>
+            where P: Fn(&ffi::Insn) -> bool {
+        let mut out = Vec::new();
+        try!(self.walk_insts(code, addr, |insn| if pred(insn) {
+            out.push(::OwnedInsn::from(insn));
+        }));
+        Ok(out)
+    }
+    #[must_use]
+    /// Like `walk_insts`, also calling `progress(bytes_done, next_addr)`
+    /// after every `every` instructions (never if `every` is 0)
+    pub fn walk_insts_progress<P, F>(&self, code: &[u8], addr: u64, every: usize, mut progress: P, mut f: F)
+            -> Result<(), ::CsError> where P: FnMut(usize, u64), F: FnMut(&ffi::Insn) {
+        let mut count = 0;
+        let mut done = 0;
+        self.walk_insts(code, addr, |insn| {
+            f(insn);
+            count += 1;
+            done += insn.size() as usize;
+            if every != 0 && count % every == 0 {
+                progress(done, insn.next_address());
+            }
+        })
+    }
+    /// Addresses of every instruction in `code`, without materializing the
+    /// instructions themselves
+    ///
+    /// This is a boundary sweep for coverage-style tools. If detail is on,
+    /// decoding goes through a scratch handle with it off, so no operand
+    /// detail is filled in.
+    pub fn instruction_offsets(&self, code: &[u8], addr: u64) -> Result<Vec<u64>, ::CsError> {
+        let mut offsets = Vec::new();
+        if self.detail {
+            let scratch = try!(HandleBuilder {
+                arch: self.arch,
+                mode: self.mode,
+                detail: false,
+                skipdata: self.skipdata,
+                skipdata_bytes: self.skipdata_bytes,
+                syntax: self.syntax,
+                strict: false,
+            }.build());
+            try!(scratch.walk_insts(code, addr, |insn| offsets.push(insn.address)));
+        } else {
+            try!(self.walk_insts(code, addr, |insn| offsets.push(insn.address)));
+        }
+        Ok(offsets)
+    }
+    /// The instruction ending exactly at `target_addr`, found by decoding
+    /// linearly from `region_start` (the address of `code[0]`)
+    ///
+    /// Returns None if linear decoding never lands on `target_addr`.
+    pub fn disasm_prev(&self, code: &[u8], region_start: u64, target_addr: u64)
+            -> Result<Option<::OwnedInsn>, ::CsError> {
+        if target_addr <= region_start {
+            return Ok(None);
+        }
+        let end = std::cmp::min((target_addr - region_start) as usize, code.len());
+        let mut prev = None;
+        try!(self.walk_insts(&code[..end], region_start, |insn| {
+            if insn.next_address() == target_addr {
+                prev = Some(::OwnedInsn::from(insn));
+            }
+        }));
+        Ok(prev)
+    }
+
+    /// Disassemble ARM/Thumb code, pairing each instruction with the
+    /// condition it effectively executes under
+    ///
+    /// Instructions inside a Thumb `IT` block get the condition from the
+    /// block (`t` slots take the IT condition, `e` slots its inverse); all
+    /// others get their own condition code. Requires detail and an ARM
+    /// handle (`CS_ERR_ARCH` otherwise).
+    pub fn iter_with_it_context<'a>(&'a self, code: &'a [u8], addr: u64) -> Result<ItContext<'a>, ::CsError> {
+        if self.arch != ffi::CsArch::ARCH_ARM {
+            return Err(::CsError::CS_ERR_ARCH);
+        }
+        if !self.detail {
+            return Err(::CsError::CS_ERR_DETAIL);
+        }
+        Ok(ItContext {
+            cursor: try!(self.iter_disasm(code, addr)),
+            it_block: std::collections::VecDeque::new(),
+        })
+    }
+
+    /// Length in bytes of the first instruction in `code`, without keeping
+    /// any of the decoded instruction around
+    pub fn insn_len(&self, code: &[u8], mut addr: u64) -> Result<usize, ::CsError> {
+        let mut code_ptr = code.as_ptr();
+        let mut code_sz = code.len();
+        unsafe {
+            let insn = try!(self.malloc_insn());
+            let res = if ffi::cs_disasm_iter(self.csh, &mut code_ptr, &mut code_sz, &mut addr, insn) {
+                Ok((*insn).size() as usize)
+            } else {
+                Err(ffi::cs_errno(self.csh))
+            };
+            ffi::cs_free(insn, 1);
+            res
+        }
+    }
+    /// Enable or disable CS_OPT_DETAIL at run-time
+    pub fn set_detail(&mut self, enable: bool) -> Result<(), ::CsError> {
+        try!(ffi::set_opt(self.csh, ffi::CsOptType::CS_OPT_DETAIL, ffi::optval::from_bool(enable)));
+        self.detail = enable;
+        Ok(())
+    }
+    /// Switch the engine to a different mode of the same architecture
+    /// (CS_OPT_MODE), e.g. between ARM and Thumb
+    ///
+    /// Returns `CS_ERR_MODE` without touching the engine if `mode` has flags
+    /// that don't apply to the handle's architecture.
+    pub fn reset_mode(&mut self, mode: ffi::CsMode) -> Result<(), ::CsError> {
+        if !mode.is_valid_for(self.arch) {
+            return Err(::CsError::CS_ERR_MODE);
+        }
+        try!(ffi::set_opt(self.csh, ffi::CsOptType::CS_OPT_MODE, ffi::optval::CsOptValue(mode.bits() as usize)));
+        self.mode = mode;
+        Ok(())
+    }
+    /// Architecture this handle disassembles
+    pub fn arch(&self) -> ffi::CsArch {
+        self.arch
+    }
+    /// Mode the engine is currently in
+    pub fn mode(&self) -> ffi::CsMode {
+        self.mode
+    }
+    /// Whether the engine decodes big-endian code
+    ///
+    /// Only the instruction bytes are affected: decoded operand values are
+    /// always native integers.
+    pub fn is_big_endian(&self) -> bool {
+        self.mode.contains(ffi::mode::BIG_ENDIAN)
+    }
+    /// Human-readable description of the current mode, see `CsMode::describe`
+    pub fn describe(&self) -> String {
+        self.mode.describe(self.arch)
+    }
+    /// Whether CS_OPT_DETAIL is currently enabled, i.e. whether `Insn::detail()`
+    /// can return anything at all
+    pub fn detail_enabled(&self) -> bool {
+        self.detail
+    }
+    /// Change the assembly output syntax (CS_OPT_SYNTAX) at run-time
+    pub fn set_syntax(&mut self, syntax: ffi::CsOptValue) -> Result<(), ::CsError> {
+        try!(ffi::set_opt(self.csh, ffi::CsOptType::CS_OPT_SYNTAX, syntax));
+        self.syntax = syntax;
+        Ok(())
+    }
+    /// The assembly output syntax in effect
+    pub fn syntax(&self) -> ffi::CsOptValue {
+        self.syntax
+    }
+    /// Enable or disable CS_OPT_SKIPDATA at run-time
+    pub fn set_skipdata(&mut self, enable: bool) -> Result<(), ::CsError> {
+        try!(ffi::set_opt(self.csh, ffi::CsOptType::CS_OPT_SKIPDATA, ffi::optval::from_bool(enable)));
+        self.skipdata = enable;
+        Ok(())
+    }
+    /// Open a new, independent handle with the same arch, mode and options
+    pub fn try_clone(&self) -> Result<Handle, ::CsError> {
+        HandleBuilder {
+            arch: self.arch,
+            mode: self.mode,
+            detail: self.detail,
+            skipdata: self.skipdata,
+            skipdata_bytes: self.skipdata_bytes,
+            syntax: self.syntax,
+            strict: false,
+        }.build()
+    }
+    /// Format instructions as an `objdump -d` style listing, one line per
+    /// instruction: address, hex bytes padded to a fixed column, then text
+    pub fn format_listing(&self, insns: &Instructions) -> String {
+        let mut out = String::new();
+        for insn in insns.iter() {
+            let bytes: Vec<String> = insn.bytes().iter().map(|b| format!("{:02x}", b)).collect();
+            let text = match insn.op_str() {
+                Some("") | None => insn.mnemonic().unwrap_or("").to_string(),
+                Some(op_str) => format!("{} {}", insn.mnemonic().unwrap_or(""), op_str),
+            };
+            out.push_str(&format!("{:>8x}: {:<21} {}\n", insn.address, bytes.join(" "), text));
+        }
+        out
+    }
+    /// Whether `insn` is a privileged (system) instruction
+    ///
+    /// Capstone 3 has no privilege group, so this goes by a list of known
+    /// x86 and ARM privileged mnemonics; detail is not needed.
+    pub fn is_privileged(&self, insn: &ffi::Insn) -> bool {
+        let known: &[&str] = match self.arch {
+            ffi::CsArch::ARCH_X86 => &["hlt", "cli", "sti", "clts", "lgdt", "lidt", "lldt", "ltr", "lmsw",
+                                       "invd", "wbinvd", "invlpg", "rdmsr", "wrmsr", "swapgs", "sysexit",
+                                       "sysret", "xsetbv", "in", "out", "insb", "insw", "insd", "outsb",
+                                       "outsw", "outsd"],
+            ffi::CsArch::ARCH_ARM => &["cps", "cpsid", "cpsie", "srsda", "srsdb", "srsia", "srsib",
+                                       "rfeda", "rfedb", "rfeia", "rfeib", "eret"],
+            ffi::CsArch::ARCH_ARM64 => &["eret", "hvc", "smc"],
+            _ => &[],
+        };
+        known.contains(&insn.mnemonic().unwrap_or(""))
+    }
+    /// Classify `insn` into a coarse category for instruction-mix
+    /// statistics
+    ///
+    /// Uses the generic groups and the arch SIMD groups (`sse*`, `avx*`,
+    /// `neon`, ...) when detail is on, then falls back to mnemonic tables
+    /// (x86 and ARM names).
+    pub fn category(&self, insn: &ffi::Insn) -> InsnCategory {
+        use ffi::CsGroup::*;
+        if let Some(detail) = insn.detail() {
+            if [CS_GRP_JUMP, CS_GRP_CALL, CS_GRP_RET, CS_GRP_INT, CS_GRP_IRET].iter().any(|&g| detail.has_group(g)) {
+                return InsnCategory::Control;
+            }
+            let simd = ["sse", "avx", "mmx", "3dnow", "fma", "neon"];
+            let is_simd = detail.group_ids().iter().filter_map(|&id| self.group_name_by_id(id))
+                                .any(|name| simd.iter().any(|prefix| name.starts_with(prefix)));
+            if is_simd {
+                return InsnCategory::Simd;
+            }
+        }
+        if self.is_privileged(insn) {
+            return InsnCategory::System;
+        }
+        let mnemonic = insn.mnemonic().unwrap_or("");
+        let tables: &[(InsnCategory, &[&str])] = &[
+            (InsnCategory::Control, &["jmp", "call", "ret", "loop", "loope", "loopne", "int", "int3", "iret",
+                                      "b", "bl", "blx", "bx", "cbz", "cbnz"]),
+            (InsnCategory::System, &["syscall", "sysenter", "cpuid", "rdtsc", "pause", "svc", "wfi",
+                                     "wfe", "mrs", "msr", "dmb", "dsb", "isb"]),
+            (InsnCategory::Memory, &["mov", "movzx", "movsx", "movsxd", "lea", "push", "pop", "xchg", "ldr",
+                                     "ldrb", "ldrh", "ldrd", "str", "strb", "strh", "strd", "ldm", "stm",
+                                     "ldp", "stp", "movw", "movt"]),
+            (InsnCategory::Arithmetic, &["add", "adc", "sub", "sbb", "sbc", "rsb", "mul", "imul", "div", "idiv",
+                                         "udiv", "sdiv", "inc", "dec", "neg", "cmp", "cmn", "mla", "mls"]),
+            (InsnCategory::Logic, &["and", "or", "orr", "xor", "eor", "not", "bic", "test", "tst", "teq",
+                                    "shl", "shr", "sal", "sar", "rol", "ror", "lsl", "lsr", "asr", "mvn"]),
+        ];
+        for &(category, names) in tables {
+            if names.contains(&mnemonic) {
+                return category;
+            }
+        }
+        let is_simd = match self.arch {
+            ffi::CsArch::ARCH_X86 => mnemonic.ends_with("ps") || mnemonic.ends_with("pd"),
+            ffi::CsArch::ARCH_ARM => mnemonic.starts_with('v'),
+            _ => false,
+        };
+        if is_simd {
+            return InsnCategory::Simd;
+        }
+        match self.arch {
+            ffi::CsArch::ARCH_X86 if mnemonic.starts_with('j') => InsnCategory::Control,
+            _ => InsnCategory::Other,
+        }
+    }
+    /// Whether `insn` does nothing: an architectural NOP (including x86
+    /// multi-byte `nop dword ptr [...]`) or a register moved or exchanged
+    /// with itself
+    ///
+    /// A 32-bit register moved to itself on x86-64 (`mov eax, eax`,
+    /// `mov r8d, r8d`) or ARM64 (`mov w0, w0`) is not a NOP, since it
+    /// clears the upper half. AT&T syntax (`nopl`, `movl %eax, %eax`) is
+    /// understood too.
+    pub fn is_nop(&self, insn: &ffi::Insn) -> bool {
+        let att = self.arch == ffi::CsArch::ARCH_X86 && self.syntax == ffi::optval::CS_OPT_SYNTAX_ATT;
+        let mnemonic = insn.mnemonic().unwrap_or("");
+        // AT&T adds an operand size suffix: nopl, movq, xchgl
+        let mnemonic = match att {
+            true => ["nop", "mov", "xchg"].iter().cloned()
+                        .find(|base| mnemonic.starts_with(base) && mnemonic.len() <= base.len() + 1 &&
+                                     mnemonic[base.len()..].chars().all(|c| "bwlq".contains(c)))
+                        .unwrap_or(mnemonic),
+            false => mnemonic,
+        };
+        if mnemonic == "nop" {
+            return true;
+        }
+        if mnemonic != "mov" && mnemonic != "xchg" {
+            return false;
+        }
+        let ops: Vec<&str> = insn.op_str().unwrap_or("").split(", ").collect();
+        if ops.len() != 2 || ops[0] != ops[1] || ops[0].contains('[') || ops[0].contains('(') ||
+           ops[0].contains('#') || ops[0].contains('$') {
+            return false;
+        }
+        let reg = ops[0].trim_start_matches('%');
+        let zero_extends = match self.arch {
+            ffi::CsArch::ARCH_X86 => self.mode.contains(ffi::mode::W64) &&
+                                     (reg.starts_with('e') || (reg.starts_with('r') && reg.ends_with('d'))),
+            ffi::CsArch::ARCH_ARM64 => reg.starts_with('w'),
+            _ => false,
+        };
+        !zero_extends
+    }
+    /// Whether `insn` is an AMD XOP-encoded instruction (`vpcmov`,
+    /// `vprotb`, ...), from its arch-specific group. Requires detail.
+    pub fn is_xop(&self, insn: &ffi::Insn) -> bool {
+        self.in_named_group(insn, "xop")
+    }
+    /// Whether `insn` is an AMD 3DNow! instruction, from its arch-specific
+    /// group. Requires detail.
+    pub fn is_3dnow(&self, insn: &ffi::Insn) -> bool {
+        self.in_named_group(insn, "3dnow")
+    }
+    fn in_named_group(&self, insn: &ffi::Insn, name: &str) -> bool {
+        match insn.detail() {
+            Some(detail) => detail.group_ids().iter().any(|&id| self.group_name_by_id(id) == Some(name)),
+            None => false,
+        }
+    }
+    /// Count instructions by length: index `n` holds the number of
+    /// `n`-byte instructions for 1 to 15, index 0 anything longer
+    pub fn size_histogram(&self, insns: &Instructions) -> [usize; 16] {
+        let mut counts = [0; 16];
+        for insn in insns.iter() {
+            let size = insn.size() as usize;
+            counts[if size < 16 { size } else { 0 }] += 1;
+        }
+        counts
+    }
+    /// Count instructions by their first byte (a prefix, if there is one)
+    pub fn opcode_frequency(&self, insns: &Instructions) -> HashMap<u8, usize> {
+        let mut counts = HashMap::new();
+        for insn in insns.iter() {
+            if let Some(&byte) = insn.bytes().first() {
+                *counts.entry(byte).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+    /// Whether `insn` writes the stack pointer, either implicitly (`push`,
+    /// `call`, ...) or as its explicit destination (`sub rsp, 8`)
+    ///
+    /// Requires detail; returns false without it. Capstone doesn't report
+    /// operand access, so an explicit x86/ARM/ARM64 stack pointer destination
+    /// counts as a write unless the instruction only compares or stores it.
+    /// ARM condition suffixes (`cmpeq`) are ignored. On ARM and ARM64 an `sp`
+    /// base register is written back by pre-indexed (`str r0, [sp, #-4]!`)
+    /// and post-indexed (`ldr x0, [sp], #16`) addressing, and by `ldm`/`stm`
+    /// with writeback (`stm sp!, {...}`).
+    pub fn modifies_sp(&self, insn: &ffi::Insn) -> bool {
+        use ffi::detail::ARMCC;
+        let detail = match insn.detail() {
+            Some(detail) => detail,
+            None => return false,
+        };
+        let is_sp = |reg: u32| match self.reg_name(reg) {
+            Some("rsp") | Some("esp") | Some("sp") | Some("wsp") => true,
+            _ => false,
+        };
+        if detail.regs_write().iter().any(|&reg| is_sp(reg as u32)) {
+            return true;
+        }
+        let mnemonic = insn.mnemonic().unwrap_or("");
+        let ops = self.operands(insn);
+        let first_is_sp = match ops.first() {
+            Some(&ffi::Operand::Reg(reg)) => is_sp(reg),
+            _ => false,
+        };
+        // A memory operand followed by another operand is post-indexed
+        let sp_base_updated = |writeback: bool| ops.iter().enumerate().any(|(i, op)| match *op {
+            ffi::Operand::Mem(ref mem) => is_sp(mem.base) && (writeback || i + 1 < ops.len()),
+            _ => false,
+        });
+        match self.arch {
+            ffi::CsArch::ARCH_X86 => first_is_sp && !["cmp", "test", "bt"].contains(&mnemonic),
+            ffi::CsArch::ARCH_ARM => {
+                let arm = unsafe { detail.data_arm() };
+                let mnemonic = mnemonic.trim_end_matches(".w").trim_end_matches(".n");
+                let mnemonic = match arm.cc {
+                    ARMCC::ARM_CC_AL | ARMCC::ARM_CC_INVALID => mnemonic,
+                    _ if mnemonic.len() > 2 => &mnemonic[..mnemonic.len() - 2],
+                    _ => mnemonic,
+                };
+                if mnemonic.starts_with("ldm") || mnemonic.starts_with("stm") {
+                    // Loading sp from the register list writes it too
+                    let loads_sp = mnemonic.starts_with("ldm") && ops.iter().skip(1).any(|op| match *op {
+                        ffi::Operand::Reg(reg) => is_sp(reg),
+                        _ => false,
+                    });
+                    return (first_is_sp && arm.writeback) || loads_sp;
+                }
+                if sp_base_updated(arm.writeback) {
+                    return true;
+                }
+                let read_only = ["cmp", "cmn", "tst", "teq"].contains(&mnemonic) || mnemonic.starts_with("str");
+                first_is_sp && !read_only
+            }
+            ffi::CsArch::ARCH_ARM64 => {
+                let arm64 = unsafe { detail.data_arm64() };
+                if sp_base_updated(arm64.writeback) {
+                    return true;
+                }
+                let read_only = ["cmp", "cmn", "tst"].contains(&mnemonic) || mnemonic.starts_with("st");
+                first_is_sp && !read_only
+            }
+            _ => false,
+        }
+    }
+    /// Absolute address referenced by a RIP-relative memory operand of
+    /// `insn` (`[rip + disp]`), or None if it has none
+    ///
+    /// x86 only; requires detail.
+    pub fn rip_target(&self, insn: &ffi::Insn) -> Option<u64> {
+        if self.arch != ffi::CsArch::ARCH_X86 {
+            return None;
+        }
+        let detail = match insn.detail() {
+            Some(detail) => unsafe { detail.data_x86() },
+            None => return None,
+        };
+        detail.operands().iter().filter_map(|op| match op.data() {
+            ffi::detail::X86OpData::Mem(mem) if self.reg_name(mem.base) == Some("rip") => {
+                Some(insn.next_address().wrapping_add(mem.disp as u64))
+            }
+            _ => None,
+        }).next()
+    }
+    /// Absolute target of a direct jump or call on x86, ARM or ARM64, or
+    /// None for indirect branches and non-branches
+    ///
+    /// Requires detail.
+    pub fn direct_target(&self, insn: &ffi::Insn) -> Option<u64> {
+        let detail = match insn.detail() {
+            Some(detail) => detail,
+            None => return None,
+        };
+        let mnemonic = insn.mnemonic().unwrap_or("");
+        let is_branch = detail.has_group(ffi::CsGroup::CS_GRP_JUMP) ||
+                        detail.has_group(ffi::CsGroup::CS_GRP_CALL) ||
+                        (self.arch == ffi::CsArch::ARCH_ARM && ["b", "bl", "blx"].contains(&mnemonic));
+        if !is_branch {
+            return None;
+        }
+        match self.arch {
+            ffi::CsArch::ARCH_X86 => match self.operands(insn).first() {
+                Some(&ffi::Operand::Imm(imm)) => Some(imm as u64),
+                _ => None,
+            },
+            ffi::CsArch::ARCH_ARM => match self.operands(insn).last() {
+                Some(&ffi::Operand::Imm(imm)) => Some(imm as u32 as u64),
+                _ => None,
+            },
+            ffi::CsArch::ARCH_ARM64 => unsafe { detail.data_arm64() }.operands().last().and_then(|op| {
+                match op.ty {
+                    ffi::detail::Arm64OpType::ARM64_OP_IMM => Some(op.data[0]),
+                    _ => None,
+                }
+            }),
+            _ => None,
+        }
+    }
+    /// The PAC or BTI operation of an ARM64 HINT-space instruction (`bti c`,
+    /// `paciasp`, ...), or None for anything else
+    ///
+    /// Decoded from the encoding, since the Capstone 3 detail layout carries
+    /// no pointer-auth or BTI information and older engines print these as
+    /// plain `hint #n`.
+    pub fn arm64_hint(&self, insn: &ffi::Insn) -> Option<ffi::detail::Arm64Hint> {
+        use ffi::detail::Arm64Hint::*;
+        let bytes = insn.bytes();
+        if self.arch != ffi::CsArch::ARCH_ARM64 || bytes.len() != 4 {
+            return None;
+        }
+        let word = match self.mode.contains(ffi::mode::BIG_ENDIAN) {
+            true => bytes.iter().fold(0u32, |acc, &b| acc << 8 | b as u32),
+            false => bytes.iter().rev().fold(0u32, |acc, &b| acc << 8 | b as u32),
+        };
+        // HINT #imm: 1101 0101 0000 0011 0010 CRm op2 11111
+        if word & 0xfffff01f != 0xd503201f {
+            return None;
+        }
+        let all = [XPACLRI, PACIA1716, PACIB1716, AUTIA1716, AUTIB1716, PACIAZ, PACIASP, PACIBZ, PACIBSP,
+                   AUTIAZ, AUTIASP, AUTIBZ, AUTIBSP, BTI, BTI_C, BTI_J, BTI_JC];
+        let imm = (word >> 5) & 0x7f;
+        all.iter().cloned().find(|&hint| hint as u32 == imm)
+    }
+    /// Addresses control can flow to after `insn`: the fall-through and/or
+    /// the direct branch target. Returns have none; calls fall through.
+    ///
+    /// Without detail every instruction just falls through.
+    pub fn successors(&self, insn: &ffi::Insn) -> Vec<u64> {
+        use ffi::CsGroup::*;
+        let next = insn.next_address();
+        let detail = match insn.detail() {
+            Some(detail) => detail,
+            None => return vec![next],
+        };
+        if detail.has_group(CS_GRP_RET) || detail.has_group(CS_GRP_IRET) {
+            return Vec::new();
+        }
+        if !detail.has_group(CS_GRP_JUMP) {
+            return vec![next];
+        }
+        let mnemonic = insn.mnemonic().unwrap_or("");
+        let unconditional = match self.arch {
+            ffi::CsArch::ARCH_X86 => mnemonic == "jmp" || mnemonic == "ljmp",
+            ffi::CsArch::ARCH_ARM => match unsafe { detail.data_arm() }.cc {
+                ffi::detail::ARMCC::ARM_CC_AL | ffi::detail::ARMCC::ARM_CC_INVALID => true,
+                _ => false,
+            },
+            ffi::CsArch::ARCH_ARM64 => ["b", "br"].contains(&mnemonic),
+            _ => false,
+        };
+        let mut out = Vec::new();
+        if !unconditional {
+            out.push(next);
+        }
+        if let Some(target) = self.direct_target(insn) {
+            out.push(target);
+        }
+        out
+    }
+    /// Control-flow edges `(from, to)` between instruction addresses, from
+    /// `successors`, for feeding into a graph library
+    #[cfg(feature = "cfg")]
+    pub fn edge_list(&self, insns: &Instructions) -> Vec<(u64, u64)> {
+        insns.iter()
+            .flat_map(|insn| self.successors(insn).into_iter().map(move |to| (insn.address, to)))
+            .collect()
+    }
+    /// Whether `insn` is a branch whose target is encoded relative to its
+    /// own address, i.e. needs no fixing up when the code is relocated as a
+    /// whole
+    ///
+    /// Capstone 3 has no relative branch group, so direct branches (see
+    /// `direct_target`) count as relative, except x86 far jumps and calls.
+    /// Requires detail.
+    pub fn is_relative_branch(&self, insn: &ffi::Insn) -> bool {
+        if self.direct_target(insn).is_none() {
+            return false;
+        }
+        match self.arch {
+            // ljmp/lcall ptr16:32 carry a segment and an absolute offset
+            ffi::CsArch::ARCH_X86 => self.num_operands(insn) == 1,
+            _ => true,
+        }
+    }
+    /// Literal pool address loaded by an ARM PC-relative memory operand of
+    /// `insn` (`ldr r0, [pc, #8]`), or None if it has none
+    ///
+    /// Accounts for PC reading as the instruction address + 8 in ARM mode,
+    /// and + 4 (word-aligned) in Thumb. ARM only; requires detail.
+    pub fn pc_relative_target(&self, insn: &ffi::Insn) -> Option<u64> {
+        use ffi::detail::ARMReg;
+        if self.arch != ffi::CsArch::ARCH_ARM {
+            return None;
+        }
+        let detail = match insn.detail() {
+            Some(detail) => unsafe { detail.data_arm() },
+            None => return None,
+        };
+        let pc = match self.mode.contains(ffi::mode::THUMB) {
+            true => (insn.address + 4) & !3,
+            false => insn.address + 8,
+        };
+        detail.operands().iter().filter_map(|op| op.mem()).find(|mem_ref| {
+            mem_ref.mem.base == ARMReg::ARM_REG_PC as u32 && mem_ref.mem.index == 0
+        }).map(|mem_ref| {
+            let disp = mem_ref.mem.disp as i64;
+            let disp = if mem_ref.subtracted && disp > 0 { -disp } else { disp };
+            pc.wrapping_add(disp as u64)
+        })
+    }
+    /// Byte offset within `insn` and current value of the 32-bit
+    /// displacement of its RIP-relative memory operand, for patching after
+    /// relocating the instruction
+    ///
+    /// The offset is worked out from the encoding Capstone reports (legacy
+    /// prefixes, REX, opcode and ModRM; RIP-relative addressing never has a
+    /// SIB byte). Returns None if the bytes found there don't hold the
+    /// displacement, e.g. for VEX-encoded instructions. x86 only; requires
+    /// detail.
+    pub fn rip_disp(&self, insn: &ffi::Insn) -> Option<(usize, i32)> {
+        let target = match self.rip_target(insn) {
+            Some(target) => target,
+            None => return None,
+        };
+        let detail = match insn.detail() {
+            Some(detail) => unsafe { detail.data_x86() },
+            None => return None,
+        };
+        let disp = target.wrapping_sub(insn.next_address()) as i32;
+        let prefixes = detail.prefix.iter().filter(|&&b| b != 0).count();
+        let rex = if detail.rex != 0 { 1 } else { 0 };
+        // Unused opcode bytes are zero, but a one-byte opcode may be 0x00
+        let opcode = std::cmp::max(detail.opcode.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1), 1);
+        let offset = prefixes + rex + opcode + 1;
+        let bytes = insn.bytes();
+        if bytes.len() < offset + 4 {
+            return None;
+        }
+        let field = (0..4).fold(0u32, |acc, i| acc | (bytes[offset + i] as u32) << (8 * i));
+        if field as i32 != disp {
+            return None;
+        }
+        Some((offset, disp))
+    }
+    /// Get the underlying Capstone handle, for calling into the C API directly
+    ///
+    /// # Safety
+    ///
+    /// The handle is still owned by this `Handle`. Callers must not close it
+    /// with `cs_close` (`Drop` would close it again), must not change options
+    /// with `cs_option` (the detail, mode and syntax this `Handle` tracks would
+    /// go stale), and must not use it after this `Handle` is dropped.
+    pub unsafe fn as_raw(&self) -> ffi::CsHandle {
+        self.csh
+    }
     /// Get the human-readable name of an instruction group
     pub fn group_name(&self, group: ffi::CsGroup) -> Option<&str> {
-        ffi::group_name(self.0, group)
+        ffi::group_name(self.csh, group)
+    }
+    /// Get the human-readable name of a group by raw id, including
+    /// arch-specific groups
+    pub fn group_name_by_id(&self, group: u8) -> Option<&str> {
+        ffi::group_name_by_id(self.csh, group)
+    }
+    /// Name of a group by raw id, together with its generic `CsGroup` when
+    /// it is one of the architecture-independent groups
+    pub fn group_info(&self, id: u8) -> GroupInfo {
+        GroupInfo {
+            name: self.group_name_by_id(id).map(|name| name.to_string()),
+            generic: ffi::CsGroup::from_id(id),
+        }
+    }
+    /// Get the human-readable name of a register
+    pub fn reg_name(&self, reg: u32) -> Option<&str> {
+        ffi::reg_name(self.csh, reg)
+    }
+    /// Explicit operands of `insn` in the architecture independent
+    /// `Operand` form, read with this handle's architecture
+    ///
+    /// Requires detail; empty without it.
+    pub fn operands(&self, insn: &ffi::Insn) -> Vec<ffi::Operand> {
+        match insn.detail() {
+            Some(detail) => unsafe { detail.operands(self.arch) },
+            None => Vec::new(),
+        }
+    }
+    /// Number of explicit operands of `insn`, read with this handle's
+    /// architecture
+    ///
+    /// Requires detail; 0 without it.
+    pub fn num_operands(&self, insn: &ffi::Insn) -> usize {
+        match insn.detail() {
+            Some(detail) => unsafe { detail.num_operands(self.arch) },
+            None => 0,
+        }
+    }
+    /// Ids of registers `insn` reads or writes without naming them as
+    /// explicit operands (e.g. EAX and EDX for `mul ecx`), in first-seen
+    /// order
+    ///
+    /// Requires detail; empty without it.
+    pub fn implicit_operands(&self, insn: &ffi::Insn) -> Vec<u32> {
+        let detail = match insn.detail() {
+            Some(detail) => detail,
+            None => return Vec::new(),
+        };
+        let explicit: Vec<u32> = self.operands(insn).iter().flat_map(|op| match *op {
+            ffi::Operand::Reg(reg) => vec![reg],
+            ffi::Operand::Mem(ref mem) => vec![mem.base, mem.index],
+            _ => Vec::new(),
+        }).collect();
+        let mut implicit = Vec::new();
+        for &reg in detail.regs_read().iter().chain(detail.regs_write()) {
+            let reg = reg as u32;
+            if !explicit.contains(&reg) && !implicit.contains(&reg) {
+                implicit.push(reg);
+            }
+        }
+        implicit
+    }
+    /// Names of the registers `insn` implicitly reads and writes (empty
+    /// when detail is off)
+    pub fn implicit_regs(&self, insn: &ffi::Insn) -> (Vec<String>, Vec<String>) {
+        let names = |regs: &[u8]| -> Vec<String> {
+            regs.iter()
+                .filter_map(|&reg| self.reg_name(reg as u32))
+                .map(|name| name.to_string())
+                .collect()
+        };
+        match insn.detail() {
+            Some(detail) => (names(detail.regs_read()), names(detail.regs_write())),
+            None => (Vec::new(), Vec::new()),
+        }
     }
 }
 
-#[doc(hidden)]
-impl From<ffi::CsHandle> for Handle {
-    fn from(csh: ffi::CsHandle) -> Handle {
-        Handle(csh)
+impl Handle {
+    // Only for `ffi::new_csh`, which accounts for the open handle
+    pub(crate) fn from_parts(csh: ffi::CsHandle, arch: ffi::CsArch, mode: ffi::CsMode) -> Handle {
+        Handle {
+            csh: csh,
+            arch: arch,
+            mode: mode,
+            detail: false,
+            skipdata: false,
+            skipdata_bytes: None,
+            syntax: ffi::optval::CS_OPT_SYNTAX_DEFAULT,
+        }
+    }
+
+    /// Allocate scratch space for one instruction (cs_malloc), for use with
+    /// cs_disasm_iter and release with cs_free(insn, 1)
+    ///
+    /// cs_malloc returns null when allocation fails; that must never reach
+    /// cs_disasm_iter, so it becomes `CS_ERR_MEM` here.
+    fn malloc_insn(&self) -> Result<*mut ffi::Insn, ::CsError> {
+        let insn = unsafe { ffi::cs_malloc(self.csh) };
+        if insn.is_null() {
+            return Err(::CsError::CS_ERR_MEM);
+        }
+        Ok(insn)
+    }
+
+    /// Make SKIPDATA skip `n` bytes at a time (CS_OPT_SKIPDATA_SETUP)
+    fn set_skipdata_bytes(&mut self, n: usize) -> Result<(), ::CsError> {
+        let setup = ffi::CsOptSkipdata {
+            mnemonic: ptr::null(),
+            callback: Some(skip_fixed),
+            user_data: n as *mut libc::c_void,
+        };
+        // Capstone copies the setup struct
+        let val = ffi::optval::CsOptValue(&setup as *const ffi::CsOptSkipdata as usize);
+        try!(ffi::set_opt(self.csh, ffi::CsOptType::CS_OPT_SKIPDATA_SETUP, val));
+        self.skipdata_bytes = Some(n);
+        Ok(())
     }
 }
 
 impl Drop for Handle {
     fn drop(&mut self) {
-        unsafe { ffi::cs_close(&mut self.0) };
+        unsafe { ffi::cs_close(&mut self.csh) };
+        ::mem::state().open_handles -= 1;
     }
 }
 
+/// SKIPDATA callback skipping a fixed number of bytes, passed as user_data
+extern "C" fn skip_fixed(_code: *const u8, code_size: libc::size_t, offset: libc::size_t,
+                         user_data: *mut libc::c_void) -> libc::size_t {
+    std::cmp::min(user_data as libc::size_t, code_size - offset)
+}
+
 /// Utility struct to construct a configured Capstone Engine Handle
 pub struct HandleBuilder {
     arch: ffi::CsArch,
     mode: ffi::CsMode,
     detail: bool,
     skipdata: bool,
+    skipdata_bytes: Option<usize>,
+    syntax: ffi::CsOptValue,
+    strict: bool,
 }
 
 impl HandleBuilder {
@@ -73,13 +1002,45 @@ impl HandleBuilder {
             mode: mode,
             detail: false,
             skipdata: false,
+            skipdata_bytes: None,
+            syntax: ffi::optval::CS_OPT_SYNTAX_DEFAULT,
+            strict: false,
         }
     }
+    /// Refuse to build (with `CS_ERR_VERSION`) unless the linked Capstone
+    /// has the detail layout this crate was written against
+    pub fn strict(mut self) -> HandleBuilder {
+        self.strict = true;
+        self
+    }
     /// Enable CS_OPT_SKIPDATA
     pub fn skipdata(mut self) -> HandleBuilder {
         self.skipdata = true;
         self
     }
+    /// Enable CS_OPT_SKIPDATA, skipping `n` bytes at a time over data
+    /// instead of the architecture's default stride
+    pub fn skipdata_bytes(mut self, n: usize) -> HandleBuilder {
+        self.skipdata = true;
+        self.skipdata_bytes = Some(n);
+        self
+    }
+    /// Start from one of the common configurations in `Preset`
+    pub fn preset(preset: Preset) -> HandleBuilder {
+        match preset {
+            Preset::X86_64_Intel_Detail => HandleBuilder::new(ffi::CsArch::ARCH_X86, ffi::mode::W64)
+                .syntax(ffi::optval::CS_OPT_SYNTAX_INTEL).detail(),
+            Preset::X86_32_Att => HandleBuilder::new(ffi::CsArch::ARCH_X86, ffi::mode::W32)
+                .syntax(ffi::optval::CS_OPT_SYNTAX_ATT),
+            Preset::Arm_Thumb_Detail => HandleBuilder::new(ffi::CsArch::ARCH_ARM, ffi::mode::THUMB).detail(),
+            Preset::Mips32_BE => HandleBuilder::new(ffi::CsArch::ARCH_MIPS, ffi::mode::MIPS32 | ffi::mode::BIG_ENDIAN),
+        }
+    }
+    /// Set the assembly output syntax (CS_OPT_SYNTAX)
+    pub fn syntax(mut self, syntax: ffi::CsOptValue) -> HandleBuilder {
+        self.syntax = syntax;
+        self
+    }
     /// Enable CS_OPT_DETAIL
     pub fn detail(mut self) -> HandleBuilder {
         self.detail = true;
@@ -87,45 +1048,266 @@ impl HandleBuilder {
     }
     /// Create and configure the Handle
     pub fn build(self) -> Result<Handle, ::CsError> {
-        let csh = try!(ffi::new_csh(self.arch, self.mode));
-        try!(ffi::set_opt(csh.0, ffi::CsOptType::CS_OPT_DETAIL, match self.detail {
-            true => ffi::optval::CS_OPT_ON,
-            false => ffi::optval::CS_OPT_OFF,
-        }));
-        try!(ffi::set_opt(csh.0, ffi::CsOptType::CS_OPT_SKIPDATA, match self.skipdata {
-            true => ffi::optval::CS_OPT_ON,
-            false => ffi::optval::CS_OPT_OFF,
-        }));
+        if self.strict && ffi::version().0 != ffi::CS_API_MAJOR {
+            return Err(::CsError::CS_ERR_VERSION);
+        }
+        let mut csh = try!(ffi::new_csh(self.arch, self.mode));
+        try!(csh.set_detail(self.detail));
+        try!(csh.set_skipdata(self.skipdata));
+        if let Some(n) = self.skipdata_bytes {
+            try!(csh.set_skipdata_bytes(n));
+        }
+        if self.syntax != ffi::optval::CS_OPT_SYNTAX_DEFAULT {
+            try!(csh.set_syntax(self.syntax));
+        }
         Ok(csh)
     }
 }
 
+/// Disassemble `code` with a temporary handle, for when managing a
+/// `Handle` isn't worth it
+pub fn quick_disasm(arch: ffi::CsArch, mode: ffi::CsMode, code: &[u8], addr: u64)
+        -> Result<Vec<::OwnedInsn>, ::CsError> {
+    let cs = try!(HandleBuilder::new(arch, mode).build());
+    let insns = try!(cs.disasm_at(code, addr));
+    Ok(insns.into_owned())
+}
+
+/// A resolved instruction group, from `Handle::group_info`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GroupInfo {
+    /// Name reported by the engine, if any
+    pub name: Option<String>,
+    /// The generic group, or None for arch-specific groups
+    pub generic: Option<ffi::CsGroup>,
+}
+
+/// Common arch/mode/option combinations for `HandleBuilder::preset`
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    /// x86-64, Intel syntax, detail on
+    X86_64_Intel_Detail,
+    /// 32-bit x86, AT&T syntax
+    X86_32_Att,
+    /// Thumb, detail on
+    Arm_Thumb_Detail,
+    /// 32-bit big-endian MIPS
+    Mips32_BE,
+}
+
+/// Coarse instruction category, from `Handle::category`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InsnCategory {
+    /// Integer arithmetic and comparison
+    Arithmetic,
+    /// Bitwise operations and shifts
+    Logic,
+    /// Data movement: loads, stores, moves and stack operations
+    Memory,
+    /// Jumps, calls, returns and interrupts
+    Control,
+    /// Privileged and system instructions
+    System,
+    /// Vector / SIMD instructions
+    Simd,
+    /// Anything else
+    Other,
+}
+
 /// Disassembled Capstone instructions
 pub struct Instructions {
     ptr: *const ffi::Insn,
     count: usize,
+    // Number of entries cs_disasm allocated, which `truncate` may hide some of
+    allocated: usize,
 }
 
 impl Instructions {
     #[doc(hidden)]
     pub fn from_parts(ptr: *const ffi::Insn, count: usize) -> Instructions {
+        // A null pointer or zero count is an empty result; never hand either
+        // to cs_free
+        if ptr.is_null() || count == 0 {
+            return Instructions {
+                ptr: ptr::null(),
+                count: 0,
+                allocated: 0,
+            };
+        }
         Instructions {
             ptr: ptr,
             count: count,
+            allocated: count,
         }
     }
 
+    // Keep only the first `len` instructions; all of them are still freed
+    fn truncate(&mut self, len: usize) {
+        self.count = std::cmp::min(self.count, len);
+    }
+
     pub fn as_slice(&self) -> &[ffi::Insn] {
+        if self.ptr.is_null() {
+            return &[];
+        }
         unsafe {
             std::slice::from_raw_parts(self.ptr, self.count)
         }
     }
+
+    /// Number of disassembled instructions
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Whether no instructions were disassembled
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Iterate over the disassembled instructions by reference
+    pub fn iter(&self) -> std::slice::Iter<ffi::Insn> {
+        self.as_slice().iter()
+    }
+
+    /// Copy the instructions out and free the C allocation
+    pub fn into_owned(self) -> Vec<::OwnedInsn> {
+        self.iter().map(::OwnedInsn::from).collect()
+    }
+
+    /// Flatten several results into one list, freeing each C allocation as
+    /// soon as it has been copied
+    pub fn concat(parts: Vec<Instructions>) -> Vec<::OwnedInsn> {
+        let mut out = Vec::with_capacity(parts.iter().map(|part| part.len()).sum());
+        for part in parts {
+            out.extend(part.into_owned());
+        }
+        out
+    }
+
+    /// Split into the instructions before `index` and those from it on
+    ///
+    /// Panics if `index > len()`.
+    pub fn split_at(&self, index: usize) -> (&[ffi::Insn], &[ffi::Insn]) {
+        self.as_slice().split_at(index)
+    }
+
+    /// Get the instruction at index `i`, or None if out of range
+    pub fn get(&self, i: usize) -> Option<&ffi::Insn> {
+        self.as_slice().get(i)
+    }
+
+    /// Get the instruction starting exactly at `addr`
+    pub fn insn_at(&self, addr: u64) -> Option<&ffi::Insn> {
+        self.iter().find(|insn| insn.address == addr)
+    }
+
+    /// Get the instruction whose bytes cover `addr`
+    pub fn insn_containing(&self, addr: u64) -> Option<&ffi::Insn> {
+        self.iter().find(|insn| insn.address <= addr && addr - insn.address < insn.size() as u64)
+    }
+
+    /// Get the first disassembled instruction
+    pub fn first(&self) -> Option<&ffi::Insn> {
+        self.as_slice().first()
+    }
+
+    /// Get the last disassembled instruction
+    pub fn last(&self) -> Option<&ffi::Insn> {
+        self.as_slice().last()
+    }
 }
 
 impl Drop for Instructions {
     fn drop(&mut self) {
+        if self.ptr.is_null() || self.allocated == 0 {
+            return;
+        }
         unsafe {
-            ffi::cs_free(self.ptr, self.count as libc::size_t);
+            ffi::cs_free(self.ptr, self.allocated as libc::size_t);
         }
     }
 }
+
+/// Resumable decoding position over a code buffer, from `Handle::iter_disasm`
+pub struct DecodeCursor<'a> {
+    handle: &'a Handle,
+    insn: *mut ffi::Insn,
+    code_ptr: *const u8,
+    code_sz: usize,
+    addr: u64,
+    _code: std::marker::PhantomData<&'a [u8]>,
+}
+
+impl<'a> DecodeCursor<'a> {
+    /// Address of the next instruction to decode
+    pub fn address(&self) -> u64 {
+        self.addr
+    }
+    /// Number of bytes left to decode
+    pub fn remaining(&self) -> usize {
+        self.code_sz
+    }
+}
+
+impl<'a> Iterator for DecodeCursor<'a> {
+    type Item = ::OwnedInsn;
+
+    fn next(&mut self) -> Option<::OwnedInsn> {
+        self.next_raw().map(::OwnedInsn::from)
+    }
+}
+
+impl<'a> DecodeCursor<'a> {
+    // The decoded instruction is only valid until the next call
+    fn next_raw(&mut self) -> Option<&ffi::Insn> {
+        unsafe {
+            if ffi::cs_disasm_iter(self.handle.csh, &mut self.code_ptr, &mut self.code_sz, &mut self.addr, self.insn) {
+                Some(&*self.insn)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+impl<'a> Drop for DecodeCursor<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::cs_free(self.insn, 1);
+        }
+    }
+}
+
+/// Iterator from `Handle::iter_with_it_context`
+pub struct ItContext<'a> {
+    cursor: DecodeCursor<'a>,
+    it_block: std::collections::VecDeque<ffi::detail::ARMCC>,
+}
+
+impl<'a> Iterator for ItContext<'a> {
+    type Item = (::OwnedInsn, ffi::detail::ARMCC);
+
+    fn next(&mut self) -> Option<(::OwnedInsn, ffi::detail::ARMCC)> {
+        use ffi::detail::ARMCC;
+        let it_block = &mut self.it_block;
+        self.cursor.next_raw().map(|insn| {
+            // The constructor checked the handle is ARM with detail on
+            let cc = match insn.detail() {
+                Some(detail) => unsafe { detail.data_arm() }.cc,
+                None => ARMCC::ARM_CC_AL,
+            };
+            let effective = it_block.pop_front().unwrap_or(cc);
+            let mnemonic = insn.mnemonic().unwrap_or("");
+            if mnemonic.starts_with("it") && mnemonic[2..].chars().all(|c| c == 't' || c == 'e') {
+                it_block.clear();
+                it_block.push_back(cc);
+                for slot in mnemonic[2..].chars() {
+                    it_block.push_back(if slot == 't' { cc } else { cc.inverse() });
+                }
+            }
+            (::OwnedInsn::from(insn), effective)
+        })
+    }
+}
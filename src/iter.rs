@@ -0,0 +1,154 @@
+use std::slice;
+use ffi::{Insn, CsGroup};
+use handle::Instructions;
+use owned::OwnedInsn;
+
+/// An instruction as seen by analysis passes, whether borrowed from
+/// Capstone or owned
+pub trait DecodedInsn {
+    /// Address of this instruction
+    fn address(&self) -> u64;
+
+    /// Instruction mnemonic ("" if unavailable)
+    fn mnemonic_str(&self) -> &str;
+
+    /// Whether this instruction belongs to `group`, or None if no detail
+    /// was recorded for it
+    fn member_of(&self, group: CsGroup) -> Option<bool>;
+}
+
+impl DecodedInsn for Insn {
+    fn address(&self) -> u64 {
+        self.address
+    }
+
+    fn mnemonic_str(&self) -> &str {
+        self.mnemonic().unwrap_or("")
+    }
+
+    fn member_of(&self, group: CsGroup) -> Option<bool> {
+        self.detail().map(|detail| detail.has_group(group))
+    }
+}
+
+impl DecodedInsn for OwnedInsn {
+    fn address(&self) -> u64 {
+        self.address
+    }
+
+    fn mnemonic_str(&self) -> &str {
+        &self.mnemonic
+    }
+
+    fn member_of(&self, _group: CsGroup) -> Option<bool> {
+        None
+    }
+}
+
+/// A decoded instruction sequence, however it was produced
+///
+/// Lets analysis passes accept bulk `Instructions`, slices of them (e.g. a
+/// window of a larger result) and buffered streaming output such as
+/// `iter_disasm(..).collect::<Vec<OwnedInsn>>()`.
+pub trait Disassembly {
+    /// The instruction type
+    type Insn: DecodedInsn;
+
+    /// The instructions, in decoding order
+    fn insns(&self) -> &[Self::Insn];
+
+    /// Iterate over the instructions
+    fn iter(&self) -> slice::Iter<Self::Insn> {
+        self.insns().iter()
+    }
+}
+
+impl Disassembly for Instructions {
+    type Insn = Insn;
+
+    fn insns(&self) -> &[Insn] {
+        self.as_slice()
+    }
+}
+
+impl Disassembly for [Insn] {
+    type Insn = Insn;
+
+    fn insns(&self) -> &[Insn] {
+        self
+    }
+}
+
+impl Disassembly for [OwnedInsn] {
+    type Insn = OwnedInsn;
+
+    fn insns(&self) -> &[OwnedInsn] {
+        self
+    }
+}
+
+impl Disassembly for Vec<OwnedInsn> {
+    type Insn = OwnedInsn;
+
+    fn insns(&self) -> &[OwnedInsn] {
+        self
+    }
+}
+
+/// Filtering adapters for iterators over instructions
+///
+/// ```ignore
+/// let calls = insns.iter().with_mnemonic("call").count();
+/// ```
+pub trait InsnIterExt<'a>: Iterator<Item = &'a Insn> + Sized {
+    /// Keep only instructions with the given mnemonic
+    fn with_mnemonic<'m>(self, mnemonic: &'m str) -> WithMnemonic<'m, Self> {
+        WithMnemonic {
+            iter: self,
+            mnemonic: mnemonic,
+        }
+    }
+
+    /// Keep only instructions belonging to `group` (requires detail)
+    fn in_group(self, group: CsGroup) -> InGroup<Self> {
+        InGroup {
+            iter: self,
+            group: group,
+        }
+    }
+}
+
+impl<'a, I: Iterator<Item = &'a Insn>> InsnIterExt<'a> for I {}
+
+/// Iterator returned by `InsnIterExt::with_mnemonic`
+pub struct WithMnemonic<'m, I> {
+    iter: I,
+    mnemonic: &'m str,
+}
+
+impl<'a, 'm, I: Iterator<Item = &'a Insn>> Iterator for WithMnemonic<'m, I> {
+    type Item = &'a Insn;
+
+    fn next(&mut self) -> Option<&'a Insn> {
+        let mnemonic = self.mnemonic;
+        self.iter.find(|insn| insn.mnemonic() == Some(mnemonic))
+    }
+}
+
+/// Iterator returned by `InsnIterExt::in_group`
+pub struct InGroup<I> {
+    iter: I,
+    group: CsGroup,
+}
+
+impl<'a, I: Iterator<Item = &'a Insn>> Iterator for InGroup<I> {
+    type Item = &'a Insn;
+
+    fn next(&mut self) -> Option<&'a Insn> {
+        let group = self.group;
+        self.iter.find(|insn| match insn.detail() {
+            Some(detail) => detail.has_group(group),
+            None => false,
+        })
+    }
+}
@@ -7,11 +7,22 @@ extern crate bitflags;
 pub mod ffi;
 mod handle;
 mod error;
+mod owned;
+mod cache;
+mod mem;
+mod elf;
+mod iter;
 
-pub use handle::{Handle,HandleBuilder,Instructions};
-pub use ffi::{Insn,InsnDetail,CsArch,CsGroup,mode,detail};
+pub use handle::{Handle,HandleBuilder,Instructions,Preset,DecodeCursor,ItContext,InsnCategory,GroupInfo,quick_disasm};
+pub use ffi::{Insn,InsnDetail,Operand,MemOperand,CsArch,CsGroup,Capabilities,capabilities,DetailLayout,
+              detail_layout_info,mode,detail};
 pub use mode::CsMode;
 pub use error::CsError;
+pub use owned::{OwnedInsn,OwnedDisasm,OwnedDetail,streams_equivalent};
+pub use cache::DisasmCache;
+pub use mem::set_mem_allocator;
+pub use elf::mode_from_elf_machine;
+pub use iter::{Disassembly,DecodedInsn,InsnIterExt,WithMnemonic,InGroup};
 
 #[cfg(test)]
 mod test {
@@ -20,24 +31,1305 @@ mod test {
 
     #[test]
     fn test_x86_simple() {
-    match capstone::Capstone::new(constants::CsArch::ARCH_X86,
-                                  constants::CsMode::MODE_64) {
-        Some(cs) => {
-            if let Some(insns) = cs.disasm(CODE, 0x1000, 0) {
-                assert_eq!(insns.len(), 2);
-                let is: Vec<_> = insns.iter().collect();
-                assert_eq!(is[0].mnemonic().unwrap(), "push");
-                assert_eq!(is[1].mnemonic().unwrap(), "mov");
+        match HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build() {
+            Ok(cs) => {
+                match cs.disasm(CODE, 0x1000, 0) {
+                    Ok(insns) => {
+                        assert_eq!(insns.len(), 2);
+                        let is: Vec<_> = insns.iter().collect();
+                        assert_eq!(is[0].mnemonic().unwrap(), "push");
+                        assert_eq!(is[1].mnemonic().unwrap(), "mov");
 
-                assert_eq!(is[0].address, 0x1000);
-                assert_eq!(is[1].address, 0x1001);
-            } else {
-                assert!(false, "Couldn't disasm instructions")
+                        assert_eq!(is[0].address, 0x1000);
+                        assert_eq!(is[1].address, 0x1001);
+                    },
+                    Err(err) => assert!(false, "Couldn't disasm instructions: {}", err),
+                }
+            },
+            Err(err) => {
+                assert!(false, "Couldn't create a cs engine: {}", err);
             }
-        },
-        None => {
-            assert!(false, "Couldn't create a cs engine");
         }
     }
-}
+
+    #[test]
+    fn test_runtime_toggles() {
+        let mut cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        assert!(!cs.detail_enabled());
+        assert!(cs.disasm(CODE, 0x1000, 0).unwrap().first().unwrap().detail().is_none());
+        cs.set_detail(true).unwrap();
+        assert!(cs.detail_enabled());
+        assert!(cs.disasm(CODE, 0x1000, 0).unwrap().first().unwrap().detail().is_some());
+        cs.set_skipdata(true).unwrap();
+        assert_eq!(cs.disasm(b"\xd6\x55", 0x1000, 0).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_x86_skipdata() {
+        // push rbp; two bytes of junk; mov rax, [rip + 0x13b8]
+        let code = b"\x55\xd6\xd6\x48\x8b\x05\xb8\x13\x00\x00";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().skipdata().build().unwrap();
+        let mut skipped = Vec::new();
+        cs.walk_insts(code, 0x1000, |insn| {
+            if insn.is_skipdata() {
+                assert!(insn.detail().is_none());
+                skipped.push(insn.address);
+            }
+        }).unwrap();
+        assert_eq!(skipped, vec![0x1001, 0x1002]);
+    }
+
+    #[test]
+    fn test_arm_reg_pair() {
+        use ffi::detail::ARMReg;
+        // ldrd r0, r1, [r2]; strexd r0, r2, r3, [r1]
+        let code = b"\xd0\x00\xc2\xe1\x92\x0f\xa1\xe1";
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).detail().build().unwrap();
+        let mut pairs = Vec::new();
+        cs.walk_insts(code, 0x1000, |insn| {
+            let arm = unsafe { insn.detail().unwrap().data_arm() };
+            pairs.push(arm.reg_pair(insn));
+        }).unwrap();
+        assert_eq!(pairs, vec![Some((ARMReg::ARM_REG_R0, ARMReg::ARM_REG_R1)),
+                               Some((ARMReg::ARM_REG_R2, ARMReg::ARM_REG_R3))]);
+    }
+
+    #[test]
+    fn test_random_input_never_panics() {
+        // xorshift, so the test is deterministic without pulling in a crate
+        let mut state: u32 = 0x12345678;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+        let plain = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let skip = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().skipdata().build().unwrap();
+        for _ in 0..5000 {
+            let len = (next() % 48) as usize;
+            let buf: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+            for cs in &[&plain, &skip] {
+                let mut total = 0;
+                if let Ok(insns) = cs.disasm(&buf, 0x1000, 0) {
+                    for insn in insns.as_slice() {
+                        let _ = (insn.mnemonic(), insn.op_str(), insn.detail());
+                        total += insn.size() as usize;
+                    }
+                    assert!(total <= buf.len());
+                }
+                cs.walk_insts(&buf, 0x1000, |insn| {
+                    let _ = (insn.mnemonic(), insn.op_str(), insn.detail());
+                }).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_x86_sib() {
+        // mov rax, qword ptr [rbx + rcx*4]; push rbp
+        let code = b"\x48\x8b\x04\x8b\x55";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let mut sibs = Vec::new();
+        cs.walk_insts(code, 0x1000, |insn| {
+            let x86 = unsafe { insn.detail().unwrap().data_x86() };
+            sibs.push(x86.sib().map(|(scale, _, _)| scale));
+        }).unwrap();
+        assert_eq!(sibs, vec![Some(4), None]);
+    }
+
+    #[test]
+    fn test_instructions_get() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(CODE, 0x1000, 0).unwrap();
+        assert_eq!(insns.get(0).unwrap().mnemonic(), Some("push"));
+        assert!(insns.get(99).is_none());
+        assert_eq!(insns.first().unwrap().address, 0x1000);
+        assert_eq!(insns.last().unwrap().mnemonic(), Some("mov"));
+    }
+
+    #[test]
+    fn test_instructions_iter() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(CODE, 0x1000, 0).unwrap();
+        let refs: Vec<&Insn> = insns.iter().collect();
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs[0] as *const Insn, insns.as_slice().as_ptr());
+        assert_eq!(refs[1].mnemonic(), Some("mov"));
+        assert_eq!(refs[1].address, 0x1001);
+    }
+
+    #[test]
+    fn test_insn_len() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        assert_eq!(cs.insn_len(CODE, 0x1000).unwrap(), 1);
+        assert_eq!(cs.insn_len(&CODE[1..], 0x1001).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_mode_describe() {
+        assert_eq!(mode::W64.describe(CsArch::ARCH_X86), "W64|LITTLE_ENDIAN");
+        assert_eq!((mode::THUMB | mode::BIG_ENDIAN).describe(CsArch::ARCH_ARM), "THUMB|BIG_ENDIAN");
+        assert_eq!(mode::ARM.describe(CsArch::ARCH_ARM), "ARM|LITTLE_ENDIAN");
+        assert_eq!(format!("{}", mode::W32), "W32|LITTLE_ENDIAN");
+    }
+
+    #[test]
+    fn test_disasm_cached() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let mut cache = DisasmCache::new(4);
+        let first = cs.disasm_cached(&mut cache, CODE, 0x1000).unwrap();
+        assert_eq!(cache.hits(), 0);
+        let second = cs.disasm_cached(&mut cache, CODE, 0x1000).unwrap();
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(first, second);
+        assert_eq!(second[1].mnemonic, "mov");
+        cs.disasm_cached(&mut cache, CODE, 0x2000).unwrap();
+        assert_eq!((cache.hits(), cache.misses(), cache.len()), (1, 2, 2));
+        // Same bytes and address on another arch miss the cache
+        let arm = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).build().unwrap();
+        let other = arm.disasm_cached(&mut cache, CODE, 0x1000).unwrap();
+        assert_eq!((cache.hits(), cache.misses(), cache.len()), (1, 3, 3));
+        assert!(other != first);
+
+        // Re-inserting a cached key must not evict anything else
+        let mut cache = DisasmCache::new(2);
+        cs.disasm_cached(&mut cache, CODE, 0x1000).unwrap();
+        cs.disasm_cached(&mut cache, CODE, 0x2000).unwrap();
+        cache.insert(CsArch::ARCH_X86, mode::W64, CODE, 0x2000, first.clone());
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(CsArch::ARCH_X86, mode::W64, CODE, 0x1000).is_some());
+    }
+
+    #[test]
+    fn test_num_operands() {
+        // nop; mov eax, ebx
+        let code = b"\x90\x89\xd8";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let counts: Vec<_> = insns.iter().map(|insn| cs.num_operands(insn)).collect();
+        assert_eq!(counts, vec![0, 2]);
+    }
+
+    #[test]
+    fn test_arm_mem_subtracted() {
+        use ffi::detail::ARMReg;
+        // ldr r0, [r1, -r2]
+        let code = b"\x02\x00\x11\xe7";
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let arm = unsafe { insns.first().unwrap().detail().unwrap().data_arm() };
+        let mem = arm.operands()[1].mem().unwrap();
+        assert!(mem.subtracted);
+        assert_eq!(mem.mem.base, ARMReg::ARM_REG_R1 as u32);
+        assert_eq!(mem.mem.index, ARMReg::ARM_REG_R2 as u32);
+        assert!(arm.operands()[0].mem().is_none());
+    }
+
+    #[test]
+    fn test_detail_enabled() {
+        let mut cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        assert!(cs.detail_enabled());
+        cs.set_detail(false).unwrap();
+        assert!(!cs.detail_enabled());
+        assert!(cs.disasm(CODE, 0x1000, 0).unwrap().first().unwrap().detail().is_none());
+    }
+
+    #[test]
+    fn test_unified_operands() {
+        use ffi::detail::ARMReg;
+        // mov rax, qword ptr [rbx + rcx*4]
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(b"\x48\x8b\x04\x8b", 0x1000, 0).unwrap();
+        match cs.operands(insns.first().unwrap())[..] {
+            [Operand::Reg(_), Operand::Mem(mem)] => assert_eq!((mem.scale, mem.disp), (4, 0)),
+            ref ops => assert!(false, "unexpected operands {:?}", ops),
+        }
+
+        // ldr r0, [r1, #4]
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).detail().build().unwrap();
+        let insns = cs.disasm(b"\x04\x00\x91\xe5", 0x1000, 0).unwrap();
+        let ops = cs.operands(insns.first().unwrap());
+        assert_eq!(ops[0], Operand::Reg(ARMReg::ARM_REG_R0 as u32));
+        match ops[1] {
+            Operand::Mem(mem) => assert_eq!((mem.base, mem.disp), (ARMReg::ARM_REG_R1 as u32, 4)),
+            ref op => assert!(false, "unexpected operand {:?}", op),
+        }
+
+        // ldr x0, [x1, #8]
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM64, mode::ARM).detail().build().unwrap();
+        let insns = cs.disasm(b"\x20\x04\x40\xf9", 0x1000, 0).unwrap();
+        match cs.operands(insns.first().unwrap())[..] {
+            [Operand::Reg(reg), Operand::Mem(mem)] => {
+                assert_eq!(cs.reg_name(reg), Some("x0"));
+                assert_eq!((cs.reg_name(mem.base), mem.disp), (Some("x1"), 8));
+            }
+            ref ops => assert!(false, "unexpected operands {:?}", ops),
+        }
+
+        // ld [%o0 + 8], %o1
+        let cs = HandleBuilder::new(CsArch::ARCH_SPARC, mode::BIG_ENDIAN).detail().build().unwrap();
+        let insns = cs.disasm(b"\xd2\x02\x20\x08", 0x1000, 0).unwrap();
+        match cs.operands(insns.first().unwrap())[..] {
+            [Operand::Mem(mem), Operand::Reg(reg)] => {
+                assert_eq!((cs.reg_name(mem.base), mem.disp), (Some("o0"), 8));
+                assert_eq!(cs.reg_name(reg), Some("o1"));
+            }
+            ref ops => assert!(false, "unexpected operands {:?}", ops),
+        }
+    }
+
+    #[test]
+    fn test_file_offset() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(CODE, 0x401000, 0).unwrap();
+        assert_eq!(insns.first().unwrap().file_offset(0x400000), 0x1000);
+        assert_eq!(insns.last().unwrap().file_offset(0x400000), 0x1001);
+        assert_eq!(insns.first().unwrap().file_offset(0x500000), 0);
+    }
+
+    #[test]
+    fn test_fp_operand() {
+        use ffi::detail::ARMOpData;
+        // x86 rarely reports X86_OP_FP, so exercise the ARM path: vmov.f32 s0, #1.0
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).detail().build().unwrap();
+        let insns = cs.disasm(b"\x00\x0a\xb7\xee", 0x1000, 0).unwrap();
+        let arm = unsafe { insns.first().unwrap().detail().unwrap().data_arm() };
+        assert_eq!(arm.operands()[1].data(), ARMOpData::Fp(1.0));
+    }
+
+    #[test]
+    fn test_mode_from_elf_machine() {
+        match mode_from_elf_machine(62, true) {
+            Some((CsArch::ARCH_X86, m)) => assert_eq!(m, mode::W64),
+            other => assert!(false, "unexpected {:?}", other),
+        }
+        assert!(mode_from_elf_machine(0xffff, true).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "object")]
+    fn test_disasm_section() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        assert_eq!(cs.disasm_section(CODE, 0x1000).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_disasm_range() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm_range(CODE, 0x1000, 0x1001).unwrap();
+        assert_eq!(insns.len(), 1);
+        assert_eq!(insns.first().unwrap().mnemonic(), Some("push"));
+        // An instruction starting inside the range is kept whole
+        assert_eq!(cs.disasm_range(CODE, 0x1000, 0x1002).unwrap().len(), 2);
+        assert!(cs.disasm_range(CODE, 0x1000, 0x1000).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_arm64_condition() {
+        use ffi::detail::Arm64Cc;
+        // b.eq #0x1000; b #0x1004
+        let code = b"\x00\x00\x00\x54\x00\x00\x00\x14";
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM64, mode::ARM).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let conds: Vec<_> = insns.iter()
+            .map(|insn| unsafe { insn.detail().unwrap().data_arm64() }.condition())
+            .collect();
+        assert_eq!(conds, vec![Some(Arm64Cc::ARM64_CC_EQ), None]);
+    }
+
+    #[test]
+    fn test_implicit_regs() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(CODE, 0x1000, 0).unwrap();
+        let (read, write) = cs.implicit_regs(insns.first().unwrap());
+        assert_eq!(read, vec!["rsp"]);
+        assert_eq!(write, vec!["rsp"]);
+
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(CODE, 0x1000, 0).unwrap();
+        assert_eq!(cs.implicit_regs(insns.first().unwrap()), (vec![], vec![]));
+    }
+
+    #[test]
+    fn test_as_raw() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let raw = unsafe { cs.as_raw() };
+        assert!(raw != 0);
+        assert_eq!(unsafe { ffi::cs_errno(raw) }, CsError::CS_ERR_OK);
+    }
+
+    #[test]
+    fn test_x86_segment_override() {
+        use ffi::detail::X86OpData;
+        // mov rax, qword ptr fs:[0x28]; mov rax, qword ptr [rbx + rcx*4]
+        let code = b"\x64\x48\x8b\x04\x25\x28\x00\x00\x00\x48\x8b\x04\x8b";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let segments: Vec<_> = insns.iter().map(|insn| {
+            let x86 = unsafe { insn.detail().unwrap().data_x86() };
+            match x86.operands()[1].data() {
+                X86OpData::Mem(mem) => mem.segment_name(&cs).map(|s| s.to_string()),
+                other => panic!("unexpected operand {:?}", other),
+            }
+        }).collect();
+        assert_eq!(segments, vec![Some("fs".to_string()), None]);
+    }
+
+    #[test]
+    fn test_reset_mode() {
+        let mut cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).build().unwrap();
+        assert_eq!(cs.describe(), "ARM|LITTLE_ENDIAN");
+        cs.reset_mode(mode::THUMB).unwrap();
+        assert_eq!(cs.describe(), "THUMB|LITTLE_ENDIAN");
+        // movs r0, #1 only decodes as a single 2-byte instruction in Thumb
+        assert_eq!(cs.disasm(b"\x01\x20", 0x1000, 0).unwrap().first().unwrap().size(), 2);
+        assert_eq!(cs.reset_mode(mode::W64), Err(CsError::CS_ERR_MODE));
+        assert_eq!(cs.mode(), mode::THUMB);
+        cs.reset_mode(mode::ARM).unwrap();
+        assert_eq!(cs.describe(), "ARM|LITTLE_ENDIAN");
+    }
+
+    #[test]
+    fn test_iter_filters() {
+        // mov eax, ebx; push rbp; mov ecx, 1; call 0x1000
+        let code = b"\x89\xd8\x55\xb9\x01\x00\x00\x00\xe8\xf3\xff\xff\xff";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        assert_eq!(insns.iter().with_mnemonic("mov").count(), 2);
+        let calls: Vec<_> = insns.iter().in_group(CsGroup::CS_GRP_CALL).map(|i| i.address).collect();
+        assert_eq!(calls, vec![0x1008]);
+    }
+
+    #[test]
+    fn test_multibyte_nop() {
+        // nop dword ptr [rax]; data16 nop; push rbp
+        let code = b"\x0f\x1f\x40\x00\x66\x90\x55";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        assert_eq!(insns.len(), 3);
+        let sizes: Vec<_> = insns.iter().map(|i| (i.size(), i.bytes().len())).collect();
+        assert_eq!(sizes, vec![(4, 4), (2, 2), (1, 1)]);
+        assert_eq!(insns.get(1).unwrap().bytes(), b"\x66\x90");
+    }
+
+    #[test]
+    fn test_format_listing() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(CODE, 0x401000, 0).unwrap();
+        let listing = cs.format_listing(&insns);
+        let lines: Vec<_> = listing.lines().collect();
+        assert_eq!(lines[0], "  401000: 55                    push rbp");
+        assert_eq!(lines[1], "  401001: 48 8b 05 b8 13 00 00  mov rax, qword ptr [rip + 0x13b8]");
+    }
+
+    #[test]
+    fn test_skipdata_bytes() {
+        // 16 bytes of data that never decode in 64-bit mode, then push rbp
+        let mut code = vec![0xd6; 16];
+        code.push(0x55);
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).skipdata_bytes(4).build().unwrap();
+        let insns = cs.disasm(&code, 0x1000, 0).unwrap();
+        let skipped: Vec<_> = insns.iter().filter(|i| i.is_skipdata()).map(|i| i.size()).collect();
+        assert_eq!(skipped, vec![4, 4, 4, 4]);
+        assert_eq!(insns.last().unwrap().mnemonic(), Some("push"));
+    }
+
+    #[test]
+    fn test_insn_hash() {
+        use std::collections::HashSet;
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let a = cs.disasm(CODE, 0x1000, 0).unwrap();
+        let b = cs.disasm(CODE, 0x1000, 0).unwrap();
+        let c = cs.disasm(CODE, 0x2000, 0).unwrap();
+        let mut seen = HashSet::new();
+        assert!(seen.insert(a.first().unwrap()));
+        assert!(!seen.insert(b.first().unwrap()));
+        assert!(seen.insert(c.first().unwrap()));
+        assert_eq!(seen.len(), 2);
+    }
+
+    #[test]
+    fn test_insn_containing() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(CODE, 0x1000, 0).unwrap();
+        assert_eq!(insns.insn_containing(0x1001).unwrap().mnemonic(), Some("mov"));
+        assert_eq!(insns.insn_containing(0x1007).unwrap().mnemonic(), Some("mov"));
+        assert!(insns.insn_containing(0x1008).is_none());
+        assert!(insns.insn_at(0x1002).is_none());
+        assert_eq!(insns.insn_at(0x1000).unwrap().mnemonic(), Some("push"));
+    }
+
+    #[test]
+    fn test_arm_register_list() {
+        use ffi::detail::ARMReg;
+        // push {r4, r5, lr}; ldm r0!, {r1, r2}
+        let code = b"\x30\x40\x2d\xe9\x06\x00\xb0\xe8";
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let lists: Vec<_> = insns.iter()
+            .map(|insn| unsafe { insn.detail().unwrap().data_arm() }.register_list(insn))
+            .collect();
+        assert_eq!(lists[0], vec![ARMReg::ARM_REG_R4, ARMReg::ARM_REG_R5, ARMReg::ARM_REG_LR]);
+        assert_eq!(lists[1], vec![ARMReg::ARM_REG_R1, ARMReg::ARM_REG_R2]);
+    }
+
+    #[test]
+    fn test_detail_layouts_fit() {
+        use std::mem::size_of;
+        let arch_data = size_of::<[u64; 185]>();
+        assert!(size_of::<detail::X86Detail>() <= arch_data);
+        assert!(size_of::<detail::ARMDetail>() <= arch_data);
+        assert!(size_of::<detail::Arm64Detail>() <= arch_data);
+        assert!(size_of::<detail::PPCDetail>() <= arch_data);
+    }
+
+    #[test]
+    fn test_next_address() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(CODE, 0x1000, 0).unwrap();
+        assert_eq!(insns.first().unwrap().next_address(), 0x1001);
+        assert_eq!(insns.first().unwrap().next_address(), insns.last().unwrap().address);
+        assert_eq!(insns.last().unwrap().next_address(), 0x1000 + CODE.len() as u64);
+    }
+
+    #[test]
+    fn test_disasm_enumerated() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm_enumerated(CODE, 0x1000).unwrap();
+        let (index, offset, ref insn) = insns[1];
+        assert_eq!((index, offset), (1, 1));
+        assert_eq!(insn.mnemonic, "mov");
+    }
+
+    #[test]
+    fn test_disasm_fewer_than_count() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(CODE, 0x1000, 10).unwrap();
+        assert_eq!(insns.len(), 2);
+        assert_eq!(insns.as_slice().len(), 2);
+        assert_eq!(cs.disasm(CODE, 0x1000, 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_ppc_branch_condition() {
+        use ffi::detail::PpcBc;
+        // beq 0x1010; b 0x1014
+        let code = b"\x41\x82\x00\x10\x48\x00\x00\x10";
+        let cs = HandleBuilder::new(CsArch::ARCH_PPC, mode::BIG_ENDIAN).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let conds: Vec<_> = insns.iter()
+            .map(|insn| unsafe { insn.detail().unwrap().data_ppc() }.bc())
+            .collect();
+        assert_eq!(conds, vec![Some(PpcBc::PPC_BC_EQ), None]);
+    }
+
+    #[test]
+    fn test_x86_avx_broadcast() {
+        use ffi::detail::X86AvxBcast;
+        // vaddpd zmm0, zmm1, qword ptr [rax]{1to8}
+        let code = b"\x62\xf1\xf5\x58\x58\x00";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let x86 = unsafe { insns.first().unwrap().detail().unwrap().data_x86() };
+        let bcasts: Vec<_> = x86.operands().iter().map(|op| op.avx_broadcast()).collect();
+        assert_eq!(bcasts, vec![None, None, Some(X86AvxBcast::X86_AVX_BCAST_8)]);
+        assert!(x86.operands().iter().all(|op| !op.avx_zeroing()));
+    }
+
+    #[test]
+    fn test_instruction_offsets() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        assert_eq!(cs.instruction_offsets(CODE, 0x1000).unwrap(), vec![0x1000, 0x1001]);
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        assert_eq!(cs.instruction_offsets(CODE, 0x1000).unwrap(), vec![0x1000, 0x1001]);
+        assert!(cs.detail_enabled());
+    }
+
+    #[test]
+    fn test_try_clone() {
+        let mut cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).detail().build().unwrap();
+        cs.reset_mode(mode::THUMB).unwrap();
+        let clone = cs.try_clone().unwrap();
+        drop(cs);
+        assert!(clone.detail_enabled());
+        assert_eq!(clone.describe(), "THUMB|LITTLE_ENDIAN");
+        let insns = clone.disasm(b"\x01\x20", 0x1000, 0).unwrap();
+        assert!(insns.first().unwrap().detail().is_some());
+    }
+
+    #[test]
+    fn test_modifies_sp() {
+        // push rbp; mov eax, ebx; sub rsp, 8; cmp rsp, rax
+        let code = b"\x55\x89\xd8\x48\x83\xec\x08\x48\x39\xc4";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let modifies: Vec<_> = insns.iter().map(|insn| cs.modifies_sp(insn)).collect();
+        assert_eq!(modifies, vec![true, false, true, false]);
+
+        // stm sp, {r0}; stm sp!, {r0}; cmpeq sp, r0; subeq sp, sp, #8
+        let code = b"\x01\x00\x8d\xe8\x01\x00\xad\xe8\x00\x00\x5d\x01\x08\xd0\x4d\x02";
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let modifies: Vec<_> = insns.iter().map(|insn| cs.modifies_sp(insn)).collect();
+        assert_eq!(modifies, vec![false, true, false, true]);
+
+        // ldr r0, [sp], #4; str r0, [sp, #-4]!; ldr r0, [sp, #4]
+        let insns = cs.disasm(b"\x04\x00\x9d\xe4\x04\x00\x2d\xe5\x04\x00\x9d\xe5", 0x1000, 0).unwrap();
+        let modifies: Vec<_> = insns.iter().map(|insn| cs.modifies_sp(insn)).collect();
+        assert_eq!(modifies, vec![true, true, false]);
+
+        // sub sp, sp, #0x10; stp x29, x30, [sp, #-16]!; ldr x0, [sp], #16; ldr x0, [sp, #8]
+        let code = b"\xff\x43\x00\xd1\xfd\x7b\xbf\xa9\xe0\x07\x41\xf8\xe0\x07\x40\xf9";
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM64, mode::ARM).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let modifies: Vec<_> = insns.iter().map(|insn| cs.modifies_sp(insn)).collect();
+        assert_eq!(modifies, vec![true, true, true, false]);
+    }
+
+    #[test]
+    fn test_owned_insn_serialization() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns: Vec<OwnedInsn> = cs.disasm(CODE, 0x1000, 0).unwrap().iter().map(OwnedInsn::from).collect();
+        let data = OwnedInsn::to_bytes(&insns);
+        assert_eq!(OwnedInsn::from_bytes(&data).unwrap(), insns);
+        assert!(OwnedInsn::from_bytes(&data[..data.len() - 1]).is_none());
+        assert!(OwnedInsn::from_bytes(&[]).is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_owned_insn_serialization_overflow() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let mut insns: Vec<OwnedInsn> = cs.disasm(CODE, 0x1000, 0).unwrap().iter().map(OwnedInsn::from).collect();
+        insns[0].bytes = vec![0x90; 256];
+        OwnedInsn::to_bytes(&insns);
+    }
+
+    #[test]
+    fn test_try_detail() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(CODE, 0x1000, 0).unwrap();
+        assert_eq!(insns.first().unwrap().try_detail(&cs).err(), Some(CsError::CS_ERR_DETAIL));
+
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().skipdata().build().unwrap();
+        let insns = cs.disasm(b"\xd6\x55", 0x1000, 0).unwrap();
+        assert_eq!(insns.first().unwrap().try_detail(&cs).err(), Some(CsError::CS_ERR_SKIPDATA));
+        assert!(insns.last().unwrap().try_detail(&cs).is_ok());
+    }
+
+    #[test]
+    fn test_disasm_owned_bytes() {
+        use std::collections::HashMap;
+        let mut map = HashMap::new();
+        {
+            let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+            map.insert(0x1000, cs.disasm_owned_bytes(CODE.to_vec(), 0x1000).unwrap());
+        }
+        let disasm = &map[&0x1000];
+        assert_eq!(disasm.code, CODE);
+        assert_eq!(disasm.insns.len(), 2);
+        assert_eq!(disasm.insns[0].mnemonic, "push");
+    }
+
+    #[test]
+    fn test_it_block_context() {
+        use ffi::detail::ARMCC;
+        // ite eq; moveq r0, #1; movne r0, #0; movs r1, #2
+        let code = b"\x0c\xbf\x01\x20\x00\x20\x02\x21";
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::THUMB).detail().build().unwrap();
+        let conds: Vec<_> = cs.iter_with_it_context(code, 0x1000).unwrap().map(|(_, cc)| cc).collect();
+        assert_eq!(conds[1..], [ARMCC::ARM_CC_EQ, ARMCC::ARM_CC_NE, ARMCC::ARM_CC_AL]);
+    }
+
+    #[test]
+    fn test_it_block_context_needs_arm() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        assert_eq!(cs.iter_with_it_context(CODE, 0x1000).err(), Some(CsError::CS_ERR_ARCH));
+    }
+
+    #[test]
+    fn test_rip_target() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(CODE, 0x1000, 0).unwrap();
+        assert_eq!(cs.rip_target(insns.get(0).unwrap()), None);
+        let mov = insns.get(1).unwrap();
+        assert_eq!(cs.rip_target(mov), Some(0x1001 + mov.bytes().len() as u64 + 0x13b8));
+    }
+
+    #[test]
+    fn test_preset() {
+        let cs = HandleBuilder::preset(Preset::X86_64_Intel_Detail).build().unwrap();
+        assert!(cs.detail_enabled());
+        assert_eq!(cs.syntax(), ffi::optval::CS_OPT_SYNTAX_INTEL);
+        assert_eq!(cs.mode(), mode::W64);
+    }
+
+    #[test]
+    fn test_insn_text() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let mut text: Vec<(String, String)> = Vec::new();
+        cs.walk_insts(CODE, 0x1000, |insn| text.push(insn.text())).unwrap();
+        assert_eq!(text, vec![("push".to_string(), "rbp".to_string()),
+                              ("mov".to_string(), "rax, qword ptr [rip + 0x13b8]".to_string())]);
+    }
+
+    #[test]
+    fn test_ppc_cr_fields() {
+        // cmpw cr1, r3, r4
+        let code = b"\x7c\x83\x20\x00";
+        let cs = HandleBuilder::new(CsArch::ARCH_PPC, mode::BIG_ENDIAN).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let detail = unsafe { insns.get(0).unwrap().detail().unwrap().data_ppc() };
+        assert_eq!(detail.cr_fields(), vec![1]);
+    }
+
+    #[test]
+    fn test_strict_version() {
+        assert_eq!(ffi::version().0, ffi::CS_API_MAJOR);
+        assert!(HandleBuilder::new(CsArch::ARCH_X86, mode::W64).strict().build().is_ok());
+    }
+
+    #[test]
+    fn test_x86_operand_eq() {
+        use ffi::detail::X86OpData;
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(CODE, 0x1000, 0).unwrap();
+        let mov = unsafe { insns.get(1).unwrap().detail().unwrap().data_x86() };
+        let ops = mov.operands();
+        // X86_REG_RAX in Capstone 3
+        let rax = 35u32;
+        assert_eq!(cs.reg_name(rax), Some("rax"));
+        assert_eq!(ops[0].data(), X86OpData::Reg(rax));
+        assert!(ops[0].data() != 35i64);
+        // add rsp, 0x10
+        let insns = cs.disasm(b"\x48\x83\xc4\x10", 0x1000, 0).unwrap();
+        let add = unsafe { insns.get(0).unwrap().detail().unwrap().data_x86() };
+        assert_eq!(add.operands()[1].data(), 0x10);
+        assert!(add.operands()[1].data() != X86OpData::Reg(0x10));
+    }
+
+    #[test]
+    fn test_disasm_prev() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let push = cs.disasm_prev(CODE, 0x1000, 0x1001).unwrap().unwrap();
+        assert_eq!(push.address, 0x1000);
+        assert_eq!(push.mnemonic, "push");
+        assert!(cs.disasm_prev(CODE, 0x1000, 0x1003).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_decode_cursor() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let mut cursor = cs.iter_disasm(CODE, 0x1000).unwrap();
+        assert_eq!(cursor.next().unwrap().mnemonic, "push");
+        assert_eq!(cursor.address(), 0x1001);
+        assert_eq!(cursor.remaining(), 7);
+        let mov = cursor.next().unwrap();
+        assert_eq!(mov.address, 0x1001);
+        assert_eq!(mov.mnemonic, "mov");
+        assert!(cursor.next().is_none());
+    }
+
+    #[test]
+    fn test_arch_display() {
+        assert_eq!(format!("{}", CsArch::ARCH_ARM64), "aarch64");
+        assert_eq!(CsArch::ARCH_X86.to_string(), "x86");
+    }
+
+    #[test]
+    fn test_disassembly_trait() {
+        fn count_calls<D: Disassembly + ?Sized>(d: &D) -> usize {
+            Disassembly::iter(d).filter(|insn| match insn.member_of(CsGroup::CS_GRP_CALL) {
+                Some(is_call) => is_call,
+                None => insn.mnemonic_str() == "call",
+            }).count()
+        }
+        // push rbp; call 0x1006; ret
+        let code = b"\x55\xe8\x00\x00\x00\x00\xc3";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        assert_eq!(count_calls(&insns), 1);
+        assert_eq!(count_calls(&insns.as_slice()[..1]), 0);
+        let streamed: Vec<OwnedInsn> = cs.iter_disasm(code, 0x1000).unwrap().collect();
+        assert_eq!(count_calls(&streamed), 1);
+        assert_eq!(count_calls(&streamed[1..]), 1);
+    }
+
+    #[test]
+    fn test_arm_register_shift() {
+        use ffi::detail::{ArmShift, ARMShifter, ARMReg};
+        // mov r0, r1, lsl r2
+        let code = b"\x11\x02\xa0\xe1";
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let detail = unsafe { insns.get(0).unwrap().detail().unwrap().data_arm() };
+        let shifts: Vec<_> = detail.operands().iter().filter_map(|op| op.shift()).collect();
+        assert_eq!(shifts, vec![ArmShift::Reg(ARMShifter::ARM_SFT_LSL_REG, ARMReg::ARM_REG_R2)]);
+    }
+
+    #[test]
+    fn test_instructions_split_at() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(CODE, 0x1000, 0).unwrap();
+        let (head, tail) = insns.split_at(1);
+        assert_eq!(head.len(), 1);
+        assert_eq!(head[0].mnemonic(), Some("push"));
+        assert_eq!(tail.len(), 1);
+        assert_eq!(tail[0].mnemonic(), Some("mov"));
+    }
+
+    #[test]
+    fn test_x86_address_size() {
+        // mov ax, word ptr [bx + si]; mov ax, word ptr [eax]
+        let code = b"\x8b\x00\x67\x8b\x00";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W16).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x100, 0).unwrap();
+        let plain = unsafe { insns.get(0).unwrap().detail().unwrap().data_x86() };
+        assert_eq!(plain.address_size(), 2);
+        assert_eq!(plain.operand_size(), Some(2));
+        let prefixed = unsafe { insns.get(1).unwrap().detail().unwrap().data_x86() };
+        assert_eq!(prefixed.address_size(), 4);
+    }
+
+    #[test]
+    fn test_rip_disp() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(CODE, 0x1000, 0).unwrap();
+        assert_eq!(cs.rip_disp(insns.get(1).unwrap()), Some((3, 0x13b8)));
+        assert_eq!(&CODE[1 + 3..1 + 7], b"\xb8\x13\x00\x00");
+        // mov dword ptr [rip + 0x10], 1
+        let insns = cs.disasm(b"\xc7\x05\x10\x00\x00\x00\x01\x00\x00\x00", 0x1000, 0).unwrap();
+        assert_eq!(cs.rip_disp(insns.get(0).unwrap()), Some((2, 0x10)));
+        // mov dword ptr [rip + 0x10], 0x10: the immediate matches the
+        // displacement, but the displacement still sits after the ModRM byte
+        let insns = cs.disasm(b"\xc7\x05\x10\x00\x00\x00\x10\x00\x00\x00", 0x1000, 0).unwrap();
+        assert_eq!(cs.rip_disp(insns.get(0).unwrap()), Some((2, 0x10)));
+    }
+
+    #[test]
+    fn test_disasm_linear() {
+        // push rbp; (0x06 is invalid in 64-bit mode); ret
+        let code = b"\x55\x06\xc3";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm_linear(code, 0x1000).unwrap();
+        let mnemonics: Vec<&str> = insns.iter().map(|insn| &insn.mnemonic[..]).collect();
+        assert_eq!(mnemonics, ["push", "(bad)", "ret"]);
+        assert_eq!(insns[1].address, 0x1001);
+        assert_eq!(insns[1].bytes, [0x06]);
+        let bad: Vec<bool> = insns.iter().map(|insn| insn.is_bad()).collect();
+        assert_eq!(bad, [false, true, false]);
+
+        // SKIPDATA entries (id 0) are not bad
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).skipdata().build().unwrap();
+        let insns = cs.disasm_linear(code, 0x1000).unwrap();
+        assert_eq!(insns[1].id, 0);
+        assert!(!insns[1].is_bad());
+    }
+
+    #[test]
+    fn test_arm64_extend() {
+        use ffi::detail::Arm64Extender;
+        // add x0, x1, w2, sxtw #2
+        let code = b"\x20\xc8\x22\x8b";
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM64, mode::ARM).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let detail = unsafe { insns.get(0).unwrap().detail().unwrap().data_arm64() };
+        let ops = detail.operands();
+        assert_eq!(ops[2].extend(), Some((Arm64Extender::ARM64_EXT_SXTW, 2)));
+        assert_eq!(ops[1].extend(), None);
+    }
+
+    #[test]
+    fn test_size_histogram() {
+        // push rbp; mov rax, [rip + 0x13b8]; ret
+        let code = b"\x55\x48\x8b\x05\xb8\x13\x00\x00\xc3";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let counts = cs.size_histogram(&insns);
+        assert_eq!(counts[1], 2);
+        assert_eq!(counts[7], 1);
+        assert_eq!(counts.iter().sum::<usize>(), insns.len());
+    }
+
+    #[test]
+    fn test_instructions_concat() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let first = cs.disasm(CODE, 0x1000, 0).unwrap();
+        let second = cs.disasm(b"\xc3", 0x2000, 0).unwrap();
+        let total = first.len() + second.len();
+        let merged = Instructions::concat(vec![first, second]);
+        assert_eq!(merged.len(), total);
+        assert_eq!(merged[2].address, 0x2000);
+    }
+
+    #[test]
+    fn test_is_privileged() {
+        // hlt; cli; wrmsr; mov rax, [rip + 0x13b8]
+        let code = b"\xf4\xfa\x0f\x30\x48\x8b\x05\xb8\x13\x00\x00";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let flags: Vec<bool> = insns.iter().map(|insn| cs.is_privileged(insn)).collect();
+        assert_eq!(flags, [true, true, true, false]);
+        // The mnemonic table decides, so no detail is needed
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let flags: Vec<bool> = insns.iter().map(|insn| cs.is_privileged(insn)).collect();
+        assert_eq!(flags, [true, true, true, false]);
+        // cpsid if; mov r0, r1
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).build().unwrap();
+        let insns = cs.disasm(b"\xc0\x00\x0c\xf1\x01\x00\xa0\xe1", 0x1000, 0).unwrap();
+        let flags: Vec<bool> = insns.iter().map(|insn| cs.is_privileged(insn)).collect();
+        assert_eq!(flags, [true, false]);
+    }
+
+    #[test]
+    fn test_disasm_at() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm_at(CODE, 0x1000).unwrap();
+        assert_eq!(insns.len(), 2);
+        assert_eq!(insns.last().unwrap().next_address(), 0x1000 + CODE.len() as u64);
+    }
+
+    #[test]
+    fn test_pc_relative_target() {
+        // ldr r0, [pc, #8]; mov r0, r1
+        let code = b"\x08\x00\x9f\xe5\x01\x00\xa0\xe1";
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        assert_eq!(cs.pc_relative_target(insns.get(0).unwrap()), Some(0x1000 + 8 + 8));
+        assert_eq!(cs.pc_relative_target(insns.get(1).unwrap()), None);
+    }
+
+    #[test]
+    fn test_disasm_bounded() {
+        let code: Vec<u8> = CODE.iter().cloned().cycle().take(CODE.len() * 64).collect();
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        assert_eq!(cs.disasm_bounded(&code, 0x1000, 1).unwrap().len(), 1);
+        assert_eq!(cs.disasm_bounded(&code, 0x1000, 0).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_direct_target() {
+        // call 0x2000; call rax
+        let code = b"\xe8\xfb\x0f\x00\x00\xff\xd0";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        assert_eq!(cs.direct_target(insns.get(0).unwrap()), Some(0x2000));
+        assert_eq!(cs.direct_target(insns.get(1).unwrap()), None);
+
+        // bl 0x2000
+        let code = b"\xfe\x03\x00\xeb";
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        assert_eq!(cs.direct_target(insns.get(0).unwrap()), Some(0x2000));
+    }
+
+    #[test]
+    fn test_empty_instructions_drop() {
+        let insns = Instructions::from_parts(::std::ptr::null(), 3);
+        assert!(insns.is_empty());
+        assert!(insns.first().is_none());
+        drop(insns);
+    }
+
+    #[test]
+    fn test_insn_category() {
+        // push rbp; jmp 0x1000; add rax, 1
+        let code = b"\x55\xeb\xfd\x48\x83\xc0\x01";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let categories: Vec<_> = insns.iter().map(|insn| cs.category(insn)).collect();
+        assert_eq!(categories, [InsnCategory::Memory, InsnCategory::Control, InsnCategory::Arithmetic]);
+
+        // pxor xmm0, xmm0; cmove rax, rbx; pushfq
+        let insns = cs.disasm(b"\x66\x0f\xef\xc0\x48\x0f\x44\xc3\x9c", 0x1000, 0).unwrap();
+        let categories: Vec<_> = insns.iter().map(|insn| cs.category(insn)).collect();
+        assert_eq!(categories, [InsnCategory::Simd, InsnCategory::Other, InsnCategory::Other]);
+
+        // mvn r0, r1
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).build().unwrap();
+        let insns = cs.disasm(b"\x01\x00\xe0\xe1", 0x1000, 0).unwrap();
+        assert_eq!(cs.category(insns.get(0).unwrap()), InsnCategory::Logic);
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let caps = capabilities();
+        assert!(caps.archs.contains(&CsArch::ARCH_X86));
+        assert!(!caps.diet);
+    }
+
+    #[test]
+    fn test_group_iter() {
+        // addps xmm0, xmm1
+        let code = b"\x0f\x58\xc1";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let detail = insns.get(0).unwrap().detail().unwrap();
+        let names: Vec<_> = detail.group_iter(&cs).filter_map(|(_, name)| name).collect();
+        assert!(names.contains(&"sse1"), "groups: {:?}", names);
+        // Only generic groups come back as CsGroup
+        assert!(detail.group_ids().iter().any(|&id| id >= 128));
+        assert!(detail.groups().is_empty());
+    }
+
+    #[test]
+    fn test_insn_skipdata_bytes() {
+        // push rbp; two bytes of junk; mov rax, [rip + 0x13b8]
+        let code = b"\x55\xd6\xd6\x48\x8b\x05\xb8\x13\x00\x00";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).skipdata().build().unwrap();
+        let mut data = Vec::new();
+        cs.walk_insts(code, 0x1000, |insn| {
+            if let Some(bytes) = insn.skipdata_bytes() {
+                data.extend_from_slice(bytes);
+            }
+        }).unwrap();
+        assert_eq!(data, [0xd6, 0xd6]);
+    }
+
+    #[test]
+    fn test_disasm_exact() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        assert_eq!(cs.disasm_exact(CODE, 0x1000).unwrap().len(), 2);
+        let mut junk = CODE.to_vec();
+        junk.push(0x48);
+        assert_eq!(cs.disasm_exact(&junk, 0x1000).err(), Some(CsError::CS_ERR_SKIPDATA));
+
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).skipdata().build().unwrap();
+        assert!(cs.disasm_exact(CODE, 0x1000).is_ok());
+        assert_eq!(cs.disasm_exact(b"\x55\xd6\xc3", 0x1000).err(), Some(CsError::CS_ERR_SKIPDATA));
+    }
+
+    #[test]
+    fn test_arm_coprocessor_operands() {
+        use ffi::detail::{ARMOpData, ARMReg};
+        // mrc p15, #0, r0, c1, c0, #0
+        let code = b"\x10\x0f\x11\xee";
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let detail = unsafe { insns.get(0).unwrap().detail().unwrap().data_arm() };
+        let ops: Vec<_> = detail.operands().iter().map(|op| op.data()).collect();
+        assert_eq!(ops[0], ARMOpData::Pimm(15));
+        assert_eq!(ops[2], ARMOpData::Reg(ARMReg::ARM_REG_R0));
+        assert_eq!(ops[3], ARMOpData::Cimm(1));
+        assert_eq!(ops[4], ARMOpData::Cimm(0));
+    }
+
+    #[test]
+    fn test_to_gas() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(CODE, 0x1000, 0).unwrap();
+        assert_eq!(insns.get(0).unwrap().to_gas(&cs), "pushq %rbp");
+        assert_eq!(insns.get(1).unwrap().to_gas(&cs), "movq 0x13b8(%rip), %rax");
+    }
+
+    #[test]
+    fn test_ppc_endianness() {
+        use ffi::detail::PPCOpData;
+        // li r3, 0x10 in both byte orders
+        let imm = |code: &[u8], mode: CsMode| {
+            let cs = HandleBuilder::new(CsArch::ARCH_PPC, mode).detail().build().unwrap();
+            let insns = cs.disasm(code, 0x1000, 0).unwrap();
+            let detail = unsafe { insns.get(0).unwrap().detail().unwrap().data_ppc() };
+            match detail.operands().last().unwrap().data() {
+                PPCOpData::Imm(imm) => (cs.is_big_endian(), imm),
+                _ => panic!("expected an immediate"),
+            }
+        };
+        assert_eq!(imm(b"\x38\x60\x00\x10", mode::BIG_ENDIAN), (true, 0x10));
+        assert_eq!(imm(b"\x10\x00\x60\x38", mode::LITTLE_ENDIAN), (false, 0x10));
+    }
+
+    #[test]
+    fn test_sparc_hint() {
+        use ffi::detail::SPARC_HINT_A;
+        // ba,a 0x1010; mov %o1, %l2
+        let code = b"\x30\x80\x00\x04\xa4\x10\x00\x09";
+        let cs = HandleBuilder::new(CsArch::ARCH_SPARC, mode::BIG_ENDIAN).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let branch = unsafe { insns.get(0).unwrap().detail().unwrap().data_sparc() };
+        assert_eq!(branch.hint(), SPARC_HINT_A);
+        let mov = unsafe { insns.get(1).unwrap().detail().unwrap().data_sparc() };
+        let classes: Vec<_> = mov.operands().iter().map(|op| op.reg_class(&cs)).collect();
+        assert_eq!(classes, [Some(detail::SparcRegClass::Out), Some(detail::SparcRegClass::Local)]);
+    }
+
+    #[test]
+    fn test_walk_insts_while() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let mut visited = Vec::new();
+        cs.walk_insts_while(CODE, 0x1000, |insn| {
+            visited.push(insn.address);
+            false
+        }).unwrap();
+        assert_eq!(visited, [0x1000]);
+    }
+
+    #[test]
+    fn test_arm_mem_barrier() {
+        use ffi::detail::ArmMemBarrier;
+        // dmb ish; mov r0, r1
+        let code = b"\x5b\xf0\x7f\xf5\x01\x00\xa0\xe1";
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let barriers: Vec<_> = insns.iter()
+            .map(|insn| unsafe { insn.detail().unwrap().data_arm() }.mem_barrier())
+            .collect();
+        assert_eq!(barriers, [Some(ArmMemBarrier::ARM_MB_ISH), None]);
+    }
+
+    #[test]
+    fn test_disasm_filter() {
+        // push rbp; call 0x2000; call rax; ret
+        let code = b"\x55\xe8\xfa\x0f\x00\x00\xff\xd0\xc3";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let calls = cs.disasm_filter(code, 0x1000, |insn| insn.mnemonic() == Some("call")).unwrap();
+        let addrs: Vec<u64> = calls.iter().map(|insn| insn.address).collect();
+        assert_eq!(addrs, [0x1001, 0x1006]);
+    }
+
+    #[test]
+    fn test_streams_equivalent() {
+        // push rbp; call +0; jmp -2; mov rax, [rip + 0x13b8]
+        let code = b"\x55\xe8\x00\x00\x00\x00\xeb\xfe\x48\x8b\x05\xb8\x13\x00\x00";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let at = |addr| cs.disasm(code, addr, 0).unwrap().into_owned();
+        assert!(streams_equivalent(&at(0x1000), &at(0x400000)));
+        assert!(!streams_equivalent(&at(0x1000), &at(0x1000)[1..]));
+        let mut patched = at(0x1000);
+        patched[2].op_str = "0x1000".to_string();
+        assert!(!streams_equivalent(&at(0x1000), &patched));
+        // push 0x1005 is an immediate, not a branch target
+        let push = |addr| cs.disasm(b"\x68\x05\x10\x00\x00", addr, 0).unwrap().into_owned();
+        let mut moved = push(0x400000);
+        moved[0].op_str = "0x400005".to_string();
+        assert!(!streams_equivalent(&push(0x1000), &moved));
+        // bt eax, 0x10 and bt eax, 0x20 are different bit tests, not branches
+        let bt = cs.disasm(b"\x0f\xba\xe0\x10", 0x1000, 0).unwrap().into_owned();
+        let bt_moved = cs.disasm(b"\x0f\xba\xe0\x20", 0x1010, 0).unwrap().into_owned();
+        assert!(!streams_equivalent(&bt, &bt_moved));
+        // AT&T spells the call callq
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64)
+            .syntax(ffi::optval::CS_OPT_SYNTAX_ATT).build().unwrap();
+        let at = |addr| cs.disasm(code, addr, 0).unwrap().into_owned();
+        assert_eq!(at(0x1000)[1].mnemonic, "callq");
+        assert!(streams_equivalent(&at(0x1000), &at(0x400000)));
+
+        // b #0x1008; cbz x0, #0x1008
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM64, mode::ARM).build().unwrap();
+        let code = b"\x02\x00\x00\x14\x20\x00\x00\xb4";
+        let at = |addr| cs.disasm(code, addr, 0).unwrap().into_owned();
+        assert!(streams_equivalent(&at(0x1000), &at(0x80000)));
+        assert!(at(0x1000)[0].op_str != at(0x80000)[0].op_str);
+    }
+
+    #[test]
+    fn test_implicit_operands() {
+        // mul ecx
+        let code = b"\xf7\xe1";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let names: Vec<_> = cs.implicit_operands(insns.get(0).unwrap()).into_iter()
+            .filter_map(|reg| cs.reg_name(reg))
+            .collect();
+        assert!(names.contains(&"eax") && names.contains(&"edx"), "implicit: {:?}", names);
+        assert!(!names.contains(&"ecx"));
+    }
+
+    #[test]
+    fn test_is_relative_branch() {
+        // jmp 0x1002; jmp qword ptr [rip]
+        let code = b"\xeb\x00\xff\x25\x00\x00\x00\x00";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        assert!(cs.is_relative_branch(insns.get(0).unwrap()));
+        assert!(!cs.is_relative_branch(insns.get(1).unwrap()));
+        // ljmp 8:0x1000 is direct but absolute
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W32).detail().build().unwrap();
+        let insns = cs.disasm(b"\xea\x00\x10\x00\x00\x08\x00", 0x1000, 0).unwrap();
+        assert!(!cs.is_relative_branch(insns.get(0).unwrap()));
+        // b 0x1008; bx lr
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM, mode::ARM).detail().build().unwrap();
+        let insns = cs.disasm(b"\x00\x00\x00\xea\x1e\xff\x2f\xe1", 0x1000, 0).unwrap();
+        assert!(cs.is_relative_branch(insns.get(0).unwrap()));
+        assert!(!cs.is_relative_branch(insns.get(1).unwrap()));
+        // Without detail nothing can be decided
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        assert!(!cs.is_relative_branch(insns.get(0).unwrap()));
+    }
+
+    #[test]
+    fn test_text_bytes() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(CODE, 0x1000, 0).unwrap();
+        for insn in insns.iter() {
+            assert_eq!(insn.mnemonic_bytes(), insn.mnemonic().unwrap().as_bytes());
+            assert_eq!(String::from_utf8_lossy(insn.op_str_bytes()), insn.op_str().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_x86_prefix_flags() {
+        use ffi::detail::{X86_PREFIX_REP, X86_PREFIX_LOCK};
+        // rep movsb byte ptr [rdi], byte ptr [rsi]
+        let code = b"\xf3\xa4";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let flags = unsafe { insns.get(0).unwrap().detail().unwrap().data_x86() }.prefix_flags();
+        assert!(flags.contains(X86_PREFIX_REP));
+        assert!(!flags.contains(X86_PREFIX_LOCK));
+    }
+
+    #[test]
+    fn test_disasm_function() {
+        // push rbp; pop rbp; ret; push rbp (next function)
+        let code = b"\x55\x5d\xc3\x55";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm_function(code, 0x1000, 64).unwrap();
+        assert_eq!(insns.len(), 3);
+        assert_eq!(insns[2].mnemonic, "ret");
+        assert_eq!(cs.disasm_function(code, 0x1000, 2).unwrap().len(), 2);
+
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64)
+            .syntax(ffi::optval::CS_OPT_SYNTAX_ATT).detail().build().unwrap();
+        let insns = cs.disasm_function(code, 0x1000, 64).unwrap();
+        assert_eq!(insns.len(), 3);
+        assert_eq!(insns[2].mnemonic, "retq");
+
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        assert_eq!(cs.disasm_function(code, 0x1000, 64).err(), Some(CsError::CS_ERR_DETAIL));
+    }
+
+    #[test]
+    #[cfg(feature = "cfg")]
+    fn test_edge_list() {
+        // cmp eax, 0; je 0x1006; nop; ret
+        let code = b"\x83\xf8\x00\x74\x01\x90\xc3";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        assert_eq!(cs.edge_list(&insns),
+                   [(0x1000, 0x1003), (0x1003, 0x1005), (0x1003, 0x1006), (0x1005, 0x1006)]);
+    }
+
+    #[test]
+    fn test_disasm_with_detail() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm_with_detail(CODE, 0x1000).unwrap();
+        let (ref push, ref detail) = insns[0];
+        assert_eq!(push.mnemonic, "push");
+        let writes: Vec<_> = detail.as_ref().unwrap().regs_write.iter().filter_map(|&reg| cs.reg_name(reg)).collect();
+        assert_eq!(writes, ["rsp"]);
+    }
+
+    #[test]
+    fn test_arm64_hint() {
+        use ffi::detail::Arm64Hint;
+        // bti c; paciasp; nop
+        let code = b"\x5f\x24\x03\xd5\x3f\x23\x03\xd5\x1f\x20\x03\xd5";
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM64, mode::ARM).build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let hints: Vec<_> = insns.iter().map(|insn| cs.arm64_hint(insn)).collect();
+        assert_eq!(hints, [Some(Arm64Hint::BTI_C), Some(Arm64Hint::PACIASP), None]);
+    }
+
+    #[test]
+    fn test_walk_insts_progress() {
+        let code: Vec<u8> = CODE.iter().cloned().cycle().take(CODE.len() * 4).collect();
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let mut reports = Vec::new();
+        let mut seen = 0;
+        cs.walk_insts_progress(&code, 0x1000, 3, |done, addr| reports.push((done, addr)), |_| seen += 1).unwrap();
+        assert_eq!(seen, 8);
+        assert_eq!(reports, [(9, 0x1009), (24, 0x1018)]);
+    }
+
+    #[test]
+    fn test_detail_layout_info() {
+        let layout = detail_layout_info();
+        println!("{:?}", layout);
+        assert!(layout.insn_detail > 0);
+        assert_eq!(layout.insn_detail, layout.arch_data_offset + 185 * 8);
+    }
+
+    #[test]
+    fn test_quick_disasm() {
+        let insns = quick_disasm(CsArch::ARCH_X86, mode::W64, CODE, 0x1000).unwrap();
+        let mnemonics: Vec<&str> = insns.iter().map(|insn| &insn.mnemonic[..]).collect();
+        assert_eq!(mnemonics, ["push", "mov"]);
+    }
+
+    #[test]
+    fn test_is_nop() {
+        // nop; nop dword ptr [rax]; mov rax, rax; mov eax, eax; push rbp
+        let code = b"\x90\x0f\x1f\x00\x48\x89\xc0\x89\xc0\x55";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let nops: Vec<bool> = insns.iter().map(|insn| cs.is_nop(insn)).collect();
+        assert_eq!(nops, [true, true, true, false, false]);
+
+        // mov r8d, r8d; mov r8, r8
+        let insns = cs.disasm(b"\x45\x89\xc0\x4d\x89\xc0", 0x1000, 0).unwrap();
+        let nops: Vec<bool> = insns.iter().map(|insn| cs.is_nop(insn)).collect();
+        assert_eq!(nops, [false, true]);
+
+        // nopl (%rax); movq %rax, %rax; movl %eax, %eax; movl %r8d, %r8d
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64)
+            .syntax(ffi::optval::CS_OPT_SYNTAX_ATT).build().unwrap();
+        let insns = cs.disasm(b"\x0f\x1f\x00\x48\x89\xc0\x89\xc0\x45\x89\xc0", 0x1000, 0).unwrap();
+        let nops: Vec<bool> = insns.iter().map(|insn| cs.is_nop(insn)).collect();
+        assert_eq!(nops, [true, true, false, false]);
+
+        // mov w0, w0; mov x0, x0
+        let cs = HandleBuilder::new(CsArch::ARCH_ARM64, mode::ARM).build().unwrap();
+        let insns = cs.disasm(b"\xe0\x03\x00\x2a\xe0\x03\x00\xaa", 0x1000, 0).unwrap();
+        let nops: Vec<bool> = insns.iter().map(|insn| cs.is_nop(insn)).collect();
+        assert_eq!(nops, [false, true]);
+    }
+
+    #[test]
+    fn test_opcode_frequency() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).build().unwrap();
+        let insns = cs.disasm(CODE, 0x1000, 0).unwrap();
+        let counts = cs.opcode_frequency(&insns);
+        assert_eq!(counts.get(&0x55), Some(&1));
+        assert_eq!(counts.get(&0x48), Some(&1));
+        assert_eq!(counts.len(), 2);
+    }
+
+    #[test]
+    fn test_x86_xop() {
+        use ffi::detail::X86OpData;
+        // vpcmov xmm0, xmm1, xmm2, xmm3; push rbp
+        let code = b"\x8f\xe8\x70\xa2\xc2\x30\x55";
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let insns = cs.disasm(code, 0x1000, 0).unwrap();
+        let vpcmov = insns.get(0).unwrap();
+        assert_eq!(vpcmov.mnemonic(), Some("vpcmov"));
+        assert!(cs.is_xop(vpcmov));
+        assert!(!cs.is_xop(insns.get(1).unwrap()));
+        let ops = unsafe { vpcmov.detail().unwrap().data_x86() }.operands();
+        let names: Vec<_> = ops.iter().map(|op| match op.data() {
+            X86OpData::Reg(reg) => cs.reg_name(reg),
+            _ => None,
+        }).collect();
+        assert_eq!(names, [Some("xmm0"), Some("xmm1"), Some("xmm2"), Some("xmm3")]);
+    }
+
+    #[test]
+    fn test_group_info() {
+        let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+        let call = cs.group_info(CsGroup::CS_GRP_CALL as u8);
+        assert_eq!(call.name, Some("call".to_string()));
+        assert_eq!(call.generic, Some(CsGroup::CS_GRP_CALL));
+
+        // addps xmm0, xmm1
+        let insns = cs.disasm(b"\x0f\x58\xc1", 0x1000, 0).unwrap();
+        let groups: Vec<_> = insns.get(0).unwrap().detail().unwrap().group_ids().iter()
+            .map(|&id| cs.group_info(id))
+            .collect();
+        let sse = groups.iter().find(|info| info.name == Some("sse1".to_string())).unwrap();
+        assert_eq!(sse.generic, None);
+    }
 }
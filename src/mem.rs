@@ -0,0 +1,44 @@
+use std::sync::{Mutex, MutexGuard};
+use ffi;
+
+#[doc(hidden)]
+pub struct MemState {
+    pub open_handles: usize,
+    allocator_set: bool,
+}
+
+static STATE: Mutex<MemState> = Mutex::new(MemState {
+    open_handles: 0,
+    allocator_set: false,
+});
+
+#[doc(hidden)]
+pub fn state() -> MutexGuard<'static, MemState> {
+    // The state stays consistent even if a holder panicked
+    STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Route all of Capstone's dynamic memory management through the given
+/// functions (CS_OPT_MEM)
+///
+/// The allocator is process-global and can only be installed once, before
+/// any `Handle` is opened; otherwise this returns `CS_ERR_OPTION`.
+pub fn set_mem_allocator(malloc: ffi::CsMalloc, calloc: ffi::CsCalloc, realloc: ffi::CsRealloc,
+                         free: ffi::CsFree, vsnprintf: ffi::CsVsnprintf) -> Result<(), ::CsError> {
+    let mut state = state();
+    if state.allocator_set || state.open_handles > 0 {
+        return Err(::CsError::CS_ERR_OPTION);
+    }
+    let opt = ffi::CsOptMem {
+        malloc: malloc,
+        calloc: calloc,
+        realloc: realloc,
+        free: free,
+        vsnprintf: vsnprintf,
+    };
+    // Capstone copies the function pointers, so opt only needs to live for the call
+    let val = ffi::optval::CsOptValue(&opt as *const ffi::CsOptMem as usize);
+    try!(ffi::set_opt(0, ffi::CsOptType::CS_OPT_MEM, val));
+    state.allocator_set = true;
+    Ok(())
+}
@@ -0,0 +1,228 @@
+use ffi;
+
+// `try!` for Option
+macro_rules! try_opt {
+    ($e:expr) => (match $e {
+        Some(x) => x,
+        None => return None,
+    })
+}
+
+/// A disassembled instruction that owns its data
+///
+/// Unlike `Insn`, this can outlive the `Instructions` or `walk_insts`
+/// callback it was copied from. Detail is not carried over.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OwnedInsn {
+    /// Instruction ID (0 for data skipped in SKIPDATA mode,
+    /// `OwnedInsn::BAD_ID` for undecodable bytes from `Handle::disasm_linear`)
+    pub id: u32,
+    /// Address of this instruction
+    pub address: u64,
+    /// Machine bytes of this instruction
+    pub bytes: Vec<u8>,
+    /// Instruction mnemonic (e.g., 'mov', 'push')
+    pub mnemonic: String,
+    /// Instruction operation string (e.g., 'rax', 'esp, 11')
+    pub op_str: String,
+}
+
+impl OwnedInsn {
+    /// Id of the one-byte `(bad)` entries `Handle::disasm_linear` makes for
+    /// undecodable bytes; no real instruction or skipped data uses it
+    pub const BAD_ID: u32 = 0xffff_ffff;
+
+    /// Size of this instruction
+    pub fn size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Whether this is an undecodable byte from `Handle::disasm_linear`,
+    /// as opposed to an instruction or data skipped in SKIPDATA mode
+    pub fn is_bad(&self) -> bool {
+        self.id == OwnedInsn::BAD_ID
+    }
+
+    /// Serialize instructions into a compact length-prefixed binary format
+    /// (little-endian), readable with `OwnedInsn::from_bytes`
+    ///
+    /// # Panics
+    ///
+    /// If there are more than `u32::MAX` instructions, or an instruction has
+    /// more than 255 bytes or a mnemonic or operand string over 64 KiB.
+    pub fn to_bytes(insns: &[OwnedInsn]) -> Vec<u8> {
+        assert!(insns.len() <= u32::max_value() as usize, "too many instructions to serialize");
+        let mut out = Vec::new();
+        out.extend_from_slice(&(insns.len() as u32).to_le_bytes());
+        for insn in insns {
+            assert!(insn.bytes.len() <= u8::max_value() as usize, "instruction too long to serialize");
+            out.extend_from_slice(&insn.id.to_le_bytes());
+            out.extend_from_slice(&insn.address.to_le_bytes());
+            out.push(insn.bytes.len() as u8);
+            out.extend_from_slice(&insn.bytes);
+            for text in &[&insn.mnemonic, &insn.op_str] {
+                assert!(text.len() <= u16::max_value() as usize, "instruction text too long to serialize");
+                out.extend_from_slice(&(text.len() as u16).to_le_bytes());
+                out.extend_from_slice(text.as_bytes());
+            }
+        }
+        out
+    }
+
+    /// Read instructions written by `OwnedInsn::to_bytes`, or None if the
+    /// data is truncated or malformed
+    pub fn from_bytes(data: &[u8]) -> Option<Vec<OwnedInsn>> {
+        let mut reader = Reader { data: data };
+        let count = try_opt!(reader.u32());
+        let mut insns = Vec::new();
+        for _ in 0..count {
+            let id = try_opt!(reader.u32());
+            let address = try_opt!(reader.u64());
+            let len = try_opt!(reader.take(1))[0] as usize;
+            let bytes = try_opt!(reader.take(len)).to_vec();
+            let mnemonic = try_opt!(reader.string());
+            let op_str = try_opt!(reader.string());
+            insns.push(OwnedInsn {
+                id: id,
+                address: address,
+                bytes: bytes,
+                mnemonic: mnemonic,
+                op_str: op_str,
+            });
+        }
+        if reader.data.is_empty() {
+            Some(insns)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> From<&'a ffi::Insn> for OwnedInsn {
+    fn from(insn: &ffi::Insn) -> OwnedInsn {
+        OwnedInsn {
+            id: insn.id,
+            address: insn.address,
+            bytes: insn.bytes().to_vec(),
+            mnemonic: insn.mnemonic().unwrap_or("").to_string(),
+            op_str: insn.op_str().unwrap_or("").to_string(),
+        }
+    }
+}
+
+/// Architecture-independent instruction detail that owns its data, see
+/// `Handle::disasm_with_detail`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OwnedDetail {
+    /// Registers implicitly read
+    pub regs_read: Vec<u32>,
+    /// Registers implicitly written
+    pub regs_write: Vec<u32>,
+    /// Raw group ids, including arch-specific ones
+    pub groups: Vec<u8>,
+}
+
+impl<'a> From<&'a ffi::InsnDetail> for OwnedDetail {
+    fn from(detail: &ffi::InsnDetail) -> OwnedDetail {
+        OwnedDetail {
+            regs_read: detail.regs_read().iter().map(|&reg| reg as u32).collect(),
+            regs_write: detail.regs_write().iter().map(|&reg| reg as u32).collect(),
+            groups: detail.group_ids().to_vec(),
+        }
+    }
+}
+
+/// Whether two instruction streams do the same thing, ignoring where they
+/// were loaded
+///
+/// Instructions must match pairwise on mnemonic and operands. For direct
+/// branches (x86 `j*`/`call*`/`loop*`; ARM and ARM64 `b`/`bl`/`blx` with an
+/// optional `<cc>` or `.<cc>` condition, `cbz`/`cbnz`/`tbz`/`tbnz`) a
+/// target operand (`0x...` or `#0x...`) is compared as an offset from its
+/// instruction, so relocated branches still match.
+pub fn streams_equivalent(a: &[OwnedInsn], b: &[OwnedInsn]) -> bool {
+    fn is_branch(mnemonic: &str) -> bool {
+        const CONDS: [&str; 17] = ["eq", "ne", "cs", "hs", "cc", "lo", "mi", "pl", "vs", "vc", "hi", "ls",
+                                   "ge", "lt", "gt", "le", "al"];
+        if mnemonic.starts_with('j') || mnemonic.starts_with("call") || mnemonic.starts_with("loop") ||
+           ["cbz", "cbnz", "tbz", "tbnz"].contains(&mnemonic) {
+            return true;
+        }
+        // Thumb width qualifiers: "bne.w"
+        let mnemonic = mnemonic.trim_end_matches(".w").trim_end_matches(".n");
+        ["blx", "bl", "b"].iter().any(|base| match mnemonic.strip_prefix(base) {
+            Some(cc) => {
+                let cc = cc.strip_prefix('.').unwrap_or(cc);
+                cc.is_empty() || CONDS.contains(&cc)
+            }
+            None => false,
+        })
+    }
+    // Split off a trailing target operand, e.g. "x0, #0x1008" -> ("x0, ", 0x1008)
+    fn target(op_str: &str) -> Option<(&str, u64)> {
+        let start = op_str.rfind(", ").map_or(0, |i| i + 2);
+        let last = &op_str[start..];
+        let last = last.strip_prefix('#').unwrap_or(last);
+        match last.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok().map(|target| (&op_str[..start], target)),
+            None => None,
+        }
+    }
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| {
+        x.mnemonic == y.mnemonic && (x.op_str == y.op_str || is_branch(&x.mnemonic) &&
+            match (target(&x.op_str), target(&y.op_str)) {
+                (Some((px, tx)), Some((py, ty))) => {
+                    px == py && tx.wrapping_sub(x.address) == ty.wrapping_sub(y.address)
+                }
+                _ => false,
+            })
+    })
+}
+
+/// Self-contained disassembly: the source bytes together with the
+/// instructions decoded from them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OwnedDisasm {
+    /// Address the code was disassembled at
+    pub addr: u64,
+    /// Source bytes
+    pub code: Vec<u8>,
+    /// Decoded instructions
+    pub insns: Vec<OwnedInsn>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.data.len() < n {
+            return None;
+        }
+        let (head, tail) = self.data.split_at(n);
+        self.data = tail;
+        Some(head)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take(8).map(|b| {
+            let mut buf = [0; 8];
+            buf.copy_from_slice(b);
+            u64::from_le_bytes(buf)
+        })
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = try_opt!(self.u16()) as usize;
+        self.take(len).and_then(|b| String::from_utf8(b.to_vec()).ok())
+    }
+}
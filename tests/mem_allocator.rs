@@ -0,0 +1,54 @@
+// Lives in its own test binary: the allocator can only be installed
+// before any handle is opened in the process.
+extern crate capstone;
+extern crate libc;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use capstone::{HandleBuilder, CsArch, CsError, mode};
+
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" {
+    fn vsnprintf(buf: *mut libc::c_char, len: libc::size_t, fmt: *const libc::c_char,
+                 ap: *mut libc::c_void) -> libc::c_int;
+}
+
+extern "C" fn count_malloc(size: libc::size_t) -> *mut libc::c_void {
+    ALLOCS.fetch_add(1, Ordering::SeqCst);
+    unsafe { libc::malloc(size) }
+}
+
+extern "C" fn count_calloc(nmemb: libc::size_t, size: libc::size_t) -> *mut libc::c_void {
+    ALLOCS.fetch_add(1, Ordering::SeqCst);
+    unsafe { libc::calloc(nmemb, size) }
+}
+
+extern "C" fn count_realloc(ptr: *mut libc::c_void, size: libc::size_t) -> *mut libc::c_void {
+    ALLOCS.fetch_add(1, Ordering::SeqCst);
+    unsafe { libc::realloc(ptr, size) }
+}
+
+extern "C" fn plain_free(ptr: *mut libc::c_void) {
+    unsafe { libc::free(ptr) }
+}
+
+extern "C" fn plain_vsnprintf(buf: *mut libc::c_char, len: libc::size_t, fmt: *const libc::c_char,
+                              ap: *mut libc::c_void) -> libc::c_int {
+    unsafe { vsnprintf(buf, len, fmt, ap) }
+}
+
+#[test]
+fn test_counting_allocator() {
+    capstone::set_mem_allocator(count_malloc, count_calloc, count_realloc,
+                                plain_free, plain_vsnprintf).unwrap();
+    let cs = HandleBuilder::new(CsArch::ARCH_X86, mode::W64).detail().build().unwrap();
+    let before = ALLOCS.load(Ordering::SeqCst);
+    let insns = cs.disasm(b"\x55\x48\x8b\x05\xb8\x13\x00\x00", 0x1000, 0).unwrap();
+    assert_eq!(insns.len(), 2);
+    assert!(ALLOCS.load(Ordering::SeqCst) > before);
+
+    // Too late: a handle is open and an allocator is already installed
+    assert_eq!(capstone::set_mem_allocator(count_malloc, count_calloc, count_realloc,
+                                           plain_free, plain_vsnprintf),
+               Err(CsError::CS_ERR_OPTION));
+}